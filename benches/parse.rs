@@ -0,0 +1,30 @@
+use std::hint::black_box;
+use std::io::Cursor;
+use criterion::{criterion_group, criterion_main, Criterion};
+use steamy_base::{report, State};
+
+fn input() -> [u8; 64] {
+    let mut report = [0u8; 64];
+    report[0] = 0x01;
+    report[2] = 0x01;
+    report[3] = 0x3c;
+
+    for (i, byte) in report[4..].iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+
+    report
+}
+
+fn parse(c: &mut Criterion) {
+    let report = input();
+
+    c.bench_function("fixed offsets", |b| b.iter(||
+        report::parse(black_box(&report)).unwrap()));
+
+    c.bench_function("read", |b| b.iter(||
+        State::parse(black_box(report[2]), Cursor::new(black_box(&report[4..]))).unwrap()));
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
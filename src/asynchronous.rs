@@ -0,0 +1,187 @@
+use std::collections::VecDeque;
+use std::future;
+use std::pin::Pin;
+use std::sync::{mpsc, Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+use futures_core::Stream;
+use tokio::sync::oneshot;
+
+use crate::{Controller, Details, Feedback, State, Error, Result};
+use crate::worker::{self, Command};
+
+/// How many states can be queued before the oldest ones are dropped.
+const CAPACITY: usize = 64;
+
+/// States read by the I/O thread and not consumed yet.
+#[derive(Default)]
+struct Queue {
+    states: VecDeque<Result<State>>,
+    closed: bool,
+    waker: Option<Waker>,
+}
+
+fn lock(queue: &Mutex<Queue>) -> MutexGuard<'_, Queue> {
+    queue.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The I/O thread side of the queue, closing it once the thread stops.
+struct Publisher(Arc<Mutex<Queue>>);
+
+impl Publisher {
+    fn publish(&self, result: Result<State>) {
+        let mut queue = lock(&self.0);
+
+        if queue.states.len() == CAPACITY {
+            queue.states.pop_front();
+        }
+
+        queue.states.push_back(result);
+
+        if let Some(waker) = queue.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl Drop for Publisher {
+    fn drop(&mut self) {
+        let mut queue = lock(&self.0);
+        queue.closed = true;
+
+        if let Some(waker) = queue.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Asynchronous controller.
+///
+/// The controller is moved to a dedicated I/O thread which reads the input
+/// reports and runs the commands, so any number of controllers can be served
+/// from a single runtime without blocking it. The states are yielded through
+/// the `Stream` implementation, which ends once the controller is
+/// disconnected.
+///
+/// Up to 64 states are queued, the oldest ones are dropped while the queue is
+/// full so the I/O thread keeps running the commands and the latest states
+/// are kept when they aren't consumed.
+///
+/// Commands are run between reads, see the `worker` module for how long they
+/// can wait. Dropping the asynchronous controller stops the I/O thread, the
+/// controller is then closed.
+pub struct AsyncController {
+    commands: mpsc::Sender<Command>,
+    queue: Arc<Mutex<Queue>>,
+}
+
+impl AsyncController {
+    /// Move the controller to its own I/O thread.
+    pub fn new(controller: Controller) -> AsyncController {
+        let queue = Arc::new(Mutex::new(Queue::default()));
+        let publisher = Publisher(queue.clone());
+
+        AsyncController {
+            commands: worker::spawn(controller, move |result| {
+                publisher.publish(result);
+                true
+            }),
+            queue,
+        }
+    }
+
+    fn poll_state(&self, cx: &mut Context<'_>) -> Poll<Option<Result<State>>> {
+        let mut queue = lock(&self.queue);
+
+        if let Some(result) = queue.states.pop_front() {
+            return Poll::Ready(Some(result));
+        }
+
+        if queue.closed {
+            return Poll::Ready(None);
+        }
+
+        queue.waker = Some(cx.waker().clone());
+
+        Poll::Pending
+    }
+
+    /// Run the function on the I/O thread with the controller.
+    ///
+    /// Fails with `Error::Disconnected` if the I/O thread has stopped.
+    pub async fn execute<T, F>(&self, func: F) -> Result<T>
+        where T: Send + 'static,
+              F: FnOnce(&mut Controller) -> Result<T> + Send + 'static
+    {
+        let (sender, receiver) = oneshot::channel();
+
+        self.commands.send(Box::new(move |controller: &mut Controller| {
+            let _ = sender.send(func(controller));
+        })).map_err(|_| Error::Disconnected)?;
+
+        receiver.await.map_err(|_| Error::Disconnected)?
+    }
+
+    /// Get the next state, `None` once the controller is gone.
+    pub async fn state(&mut self) -> Option<Result<State>> {
+        future::poll_fn(|cx| self.poll_state(cx)).await
+    }
+
+    /// Get the controller details.
+    pub async fn details(&self) -> Result<Details> {
+        self.execute(|controller| controller.details()).await
+    }
+
+    /// Send feedback built by the given function.
+    pub async fn feedback<F>(&self, build: F) -> Result<()>
+        where F: FnOnce(Feedback<'_>) -> Feedback<'_> + Send + 'static
+    {
+        self.execute(|controller| build(controller.feedback()).send()).await
+    }
+
+    /// Set the LED brightness.
+    pub async fn led(&self, value: u8) -> Result<()> {
+        self.execute(move |controller| controller.led().level(value)).await
+    }
+
+    /// Enable or disable the sensors.
+    pub async fn sensors(&self, enable: bool) -> Result<()> {
+        self.execute(move |controller| if enable {
+            controller.sensors().on()
+        } else {
+            controller.sensors().off()
+        }).await
+    }
+
+    /// Enable or disable lizard mode.
+    pub async fn lizard(&self, enable: bool) -> Result<()> {
+        self.execute(move |controller| if enable {
+            controller.lizard().enable()
+        } else {
+            controller.lizard().disable()
+        }).await
+    }
+
+    /// Set the idle timeout.
+    pub async fn timeout(&self, value: Duration) -> Result<()> {
+        self.execute(move |controller| controller.timeout(value)).await
+    }
+
+    /// Check if the wireless controller is connected.
+    pub async fn is_connected(&self) -> Result<bool> {
+        self.execute(|controller| Ok(controller.is_connected())).await
+    }
+
+    /// Turn the controller off.
+    pub async fn off(&self) -> Result<()> {
+        self.execute(|controller| controller.off()).await
+    }
+}
+
+impl Stream for AsyncController {
+    type Item = Result<State>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.poll_state(cx)
+    }
+}
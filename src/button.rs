@@ -25,8 +25,15 @@ bitflags! {
 
 		const STICK = 0b000000000000000001000000;
 
+		/// Alias of `PAD_AND_STICK`, the bit doesn't tell the stick is
+		/// touched.
 		const STICK_TOUCH = 0b000000000000000010000000;
 
+		/// Both the stick and the left pad are in use, reports alternate
+		/// between the two. Same bit as `STICK_TOUCH`, so `Debug` shows both
+		/// names.
+		const PAD_AND_STICK = 0b000000000000000010000000;
+
 		const TRACK = 0b000000000000000000000100;
 
 		const TRACK_TOUCH = 0b000000000000000000010000;
@@ -50,3 +57,10 @@ bitflags! {
 		const RIGHT_TRIGGER = 0b000000010000000000000000;
 	}
 }
+
+impl Button {
+	/// The bits set that don't match any known button.
+	pub fn unknown(&self) -> u32 {
+		self.bits() & !Button::all().bits()
+	}
+}
@@ -1,12 +1,12 @@
 use crate::{Controller};
-use color_eyre::{Result};
+use crate::{Result};
 
 /// Calibration manager.
 pub struct Calibrate<'a> {
     controller: &'a mut Controller,
 }
 
-impl<'a, 'b> Calibrate<'a> {
+impl<'a> Calibrate<'a> {
     #[doc(hidden)]
     pub fn new(controller: &'a mut Controller) -> Calibrate<'a> {
         Calibrate {
@@ -25,6 +25,8 @@ impl<'a, 'b> Calibrate<'a> {
     }
 
     /// Calibrate the sensors.
+    ///
+    /// The gyro bias can also be measured on the host, see `calibration`.
     pub fn sensors(self) -> Result<()> {
         self.controller.control(0xb5)
     }
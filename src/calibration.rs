@@ -0,0 +1,245 @@
+//! Host side gyro calibration.
+//!
+//! Gyros report a small rotation even when they're not moving, the bias,
+//! which makes orientations drift. The bias is measured by averaging the gyro
+//! while the controller lies still, and can be stored per controller so it's
+//! available right away on the next connection.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::{Angles, State, Error, Result};
+use crate::motion::{Vector, GYRO, ACCELERATION};
+
+/// Default amount of samples averaged, about a second of reports.
+const WINDOW: usize = 250;
+
+/// Default acceleration change in standard gravities tolerated at rest.
+const TOLERANCE: f32 = 0.01;
+
+/// Angular velocity in degrees per second above which the controller can't be
+/// at rest, whatever the bias.
+const MOVING: f32 = 10.0;
+
+/// Default fraction of each new measurement blended into the bias.
+const CONTINUOUS: f32 = 0.1;
+
+/// Gyro bias calibration.
+///
+/// The controller is considered at rest while the acceleration stays within
+/// the tolerance for a whole window, the gyro average over that window is
+/// then the bias. The first measurement is taken as is, later ones are
+/// blended in to follow the bias as the controller warms up.
+pub struct Calibration {
+    window: usize,
+    tolerance: f32,
+    continuous: Option<f32>,
+
+    bias: Option<Vector>,
+    reference: Option<Vector>,
+    sum: Vector,
+    count: usize,
+}
+
+impl Default for Calibration {
+    fn default() -> Calibration {
+        Calibration::new()
+    }
+}
+
+impl Calibration {
+    /// Create an empty calibration.
+    pub fn new() -> Calibration {
+        Calibration {
+            window: WINDOW,
+            tolerance: TOLERANCE,
+            continuous: Some(CONTINUOUS),
+
+            bias: None,
+            reference: None,
+            sum: Vector::default(),
+            count: 0,
+        }
+    }
+
+    /// Change the amount of samples averaged for a measurement.
+    pub fn window(&mut self, value: usize) {
+        self.window = value.max(1);
+        self.restart(None);
+    }
+
+    /// Change the acceleration change in standard gravities tolerated at
+    /// rest.
+    pub fn tolerance(&mut self, value: f32) {
+        self.tolerance = value;
+    }
+
+    /// Change the fraction of each new measurement blended into the bias,
+    /// `None` stops calibrating once the bias is known.
+    pub fn continuous(&mut self, value: Option<f32>) {
+        self.continuous = value;
+    }
+
+    /// The bias in degrees per second, if known.
+    pub fn bias(&self) -> Option<Vector> {
+        self.bias
+    }
+
+    /// Set the bias in degrees per second, like one loaded from a `Store`.
+    pub fn set_bias(&mut self, value: Option<Vector>) {
+        self.bias = value;
+    }
+
+    /// Check if the bias is known.
+    pub fn is_calibrated(&self) -> bool {
+        self.bias.is_some()
+    }
+
+    /// Handle a state, returning it with the bias removed from the gyro.
+    ///
+    /// Other states than `State::Input` are returned as they are.
+    pub fn update(&mut self, state: &State) -> State {
+        let mut state = *state;
+
+        let State::Input { ref mut gyro, acceleration, .. } = state else {
+            return state;
+        };
+
+        let rate = Vector::decode(*gyro, GYRO);
+        let acceleration = Vector::decode(acceleration, ACCELERATION);
+
+        self.measure(rate, acceleration);
+
+        if let Some(bias) = self.bias {
+            *gyro = encode(rate - bias);
+        }
+
+        state
+    }
+
+    fn measure(&mut self, rate: Vector, acceleration: Vector) {
+        if self.bias.is_some() && self.continuous.is_none() {
+            return;
+        }
+
+        let still = self.reference.is_some_and(|reference| (acceleration - reference).length() <= self.tolerance);
+
+        if !still || rate.length() > MOVING {
+            self.restart(Some(acceleration));
+            return;
+        }
+
+        self.sum = self.sum + rate;
+        self.count += 1;
+
+        if self.count < self.window {
+            return;
+        }
+
+        let average = self.sum * (1.0 / self.count as f32);
+
+        self.bias = Some(match (self.bias, self.continuous) {
+            (Some(bias), Some(fraction)) => bias + (average - bias) * fraction,
+            _ => average,
+        });
+
+        self.restart(Some(acceleration));
+    }
+
+    fn restart(&mut self, reference: Option<Vector>) {
+        self.reference = reference;
+        self.sum = Vector::default();
+        self.count = 0;
+    }
+}
+
+/// Encode a rate in degrees per second back to raw gyro values.
+fn encode(rate: Vector) -> Angles {
+    let raw = |value: f32| (value / GYRO).round().clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+
+    Angles {
+        pitch: raw(rate.x),
+        yaw: raw(rate.y),
+        roll: raw(rate.z),
+    }
+}
+
+/// Calibrations stored on disk, one file per controller serial number.
+///
+/// Each file holds the bias in degrees per second around the three axes on
+/// a single line.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Store {
+    path: PathBuf,
+}
+
+impl Store {
+    /// Use the given directory, created when saving.
+    pub fn new<P: AsRef<Path>>(path: P) -> Store {
+        Store {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Use the configuration directory of the user, if there's one.
+    pub fn user() -> Option<Store> {
+        let config = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+
+        Some(Store::new(config.join("steamy").join("gyro")))
+    }
+
+    /// The file for the serial number, the one found in
+    /// `Details::serial.controller`.
+    ///
+    /// Serials that aren't alphanumeric up to their first NUL are named after
+    /// all their bytes in hexadecimal.
+    pub fn file(&self, serial: &[u8; 10]) -> PathBuf {
+        let text = serial.split(|&byte| byte == 0).next().unwrap_or_default();
+
+        let name = if !text.is_empty() && text.iter().all(u8::is_ascii_alphanumeric) {
+            String::from_utf8_lossy(text).into_owned()
+        } else {
+            serial.iter().map(|byte| format!("{:02x}", byte)).collect()
+        };
+
+        self.path.join(name)
+    }
+
+    /// Load the bias for the serial number, if it was saved.
+    pub fn load(&self, serial: &[u8; 10]) -> Result<Option<Vector>> {
+        let content = match fs::read_to_string(self.file(serial)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(Error::Io(e)),
+        };
+
+        let values = content.split_whitespace()
+            .map(|value| value.parse::<f32>())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| Error::Malformed("invalid calibration"))?;
+
+        match values[..] {
+            [x, y, z] => Ok(Some(Vector::new(x, y, z))),
+            _ => Err(Error::Malformed("invalid calibration")),
+        }
+    }
+
+    /// Save the bias for the serial number.
+    ///
+    /// The file is written next to its destination and renamed into place,
+    /// so it's never left truncated.
+    pub fn save(&self, serial: &[u8; 10], bias: Vector) -> Result<()> {
+        fs::create_dir_all(&self.path)?;
+
+        let file = self.file(serial);
+        let temporary = file.with_extension("tmp");
+
+        fs::write(&temporary, format!("{} {} {}\n", bias.x, bias.y, bias.z))?;
+        fs::rename(&temporary, &file)?;
+
+        Ok(())
+    }
+}
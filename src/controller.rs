@@ -3,9 +3,11 @@ use std::thread;
 use std::io::{self, Cursor, Write};
 use byteorder::{WriteBytesExt, LittleEndian};
 
-use color_eyre::{Result};
-use color_eyre::eyre::{bail};
-use crate::{State, Details};
+use crate::{Error, Result};
+use crate::{State, Details, Descriptor};
+use crate::state::{Battery, Tracker};
+use crate::report;
+use crate::transport::{Transport, Recorder};
 use crate::{Lizard, Feedback, Sensors, Led, Sound, Calibrate, details};
 
 const LIMIT: u64 = 10;
@@ -18,9 +20,13 @@ macro_rules! request {
 				v
 			}
 
+			Err(Error::Disconnected) => {
+				return Err(Error::Disconnected);
+			}
+
 			Err(e) => {
 				if $limit == 0 {
-					Err(e)?;
+					return Err(e);
 				}
 
 				thread::sleep(Duration::from_millis((LIMIT - $limit) * INCREASE));
@@ -38,6 +44,8 @@ pub struct Settings {
     pub timeout: u16,
     pub sensors: bool,
     pub lizard: bool,
+    pub led: Option<u8>,
+    pub strict: bool,
 }
 
 impl Default for Settings {
@@ -46,30 +54,32 @@ impl Default for Settings {
             timeout: 360,
             sensors: false,
             lizard: false,
+            led: None,
+            strict: false,
         }
     }
 }
 
 #[cfg(target_os = "linux")]
-const PACKET_MAX_SIZE: usize = 64;
-const DEBUG_PACKET_SIZE: usize = 64;
+pub(crate) const PACKET_MAX_SIZE: usize = 64;
 #[cfg(target_os = "windows")]
-const PACKET_MAX_SIZE: usize = 65;
+pub(crate) const PACKET_MAX_SIZE: usize = 65;
 #[cfg(target_os = "linux")]
-const PACKET_START_IND: usize = 0;
+pub(crate) const PACKET_START_IND: usize = 0;
 #[cfg(target_os = "windows")]
-const PACKET_START_IND: usize = 1;
+pub(crate) const PACKET_START_IND: usize = 1;
 
 /// The controller.
 pub struct Controller {
-    handle: rusb::DeviceHandle<rusb::Context>,
+    transport: Box<dyn Transport>,
     packet: [u8; PACKET_MAX_SIZE],
-    debug_packet: [u8; DEBUG_PACKET_SIZE],
+    input: [u8; PACKET_MAX_SIZE],
     settings: Settings,
+    tracker: Tracker,
+    battery: Option<Battery>,
 
-    product: u16,
-    address: u8,
-    index: u16,
+    descriptor: Descriptor,
+    slot: u8,
 }
 
 // pub struct Controller {
@@ -91,60 +101,18 @@ pub struct Controller {
 // 	marker:  PhantomData<&'a ()>,
 // }
 
-pub fn find_address(
-    mut device: rusb::Device<rusb::Context>,
-    mut handle: rusb::DeviceHandle<rusb::Context>,
-    endpoint: u8,
-) -> Result<(u8, rusb::DeviceHandle<rusb::Context>)> {
-    let mut address: Option<u8> = None;
-
-    for i in 0..device.device_descriptor()?.num_configurations() {
-        for interface in device.config_descriptor(i)?.interfaces() {
-            if handle.kernel_driver_active(interface.number())? {
-                handle.detach_kernel_driver(interface.number())?;
-            }
-
-            for descriptor in interface.descriptors() {
-                if descriptor.class_code() == 3 &&
-                    descriptor.sub_class_code() == 0 &&
-                    descriptor.protocol_code() == 0
-                {
-                    handle.claim_interface(descriptor.interface_number())?;
-                }
-
-                for end in descriptor.endpoint_descriptors() {
-                    if end.number() == endpoint {
-                        address = Some(end.address());
-                    }
-                }
-            }
-        }
-    }
-    let address = address.ok_or(rusb::Error::InvalidParam)?;
-    Ok((address, handle))
-}
-
 impl Controller {
-    pub fn new(
-        mut device: rusb::Device<rusb::Context>,
-        mut handle: rusb::DeviceHandle<rusb::Context>,
-        product: u16,
-        endpoint: u8,
-        index: u16,
-    ) -> Result<Controller> {
-        let (address, handle) = find_address(device, handle, endpoint)?;
-
-        let mut controller = Controller {
-            handle,
-            packet: [0u8; PACKET_MAX_SIZE],
-            debug_packet: [0u8; DEBUG_PACKET_SIZE],
-            settings: Default::default(),
-
+    /// Create a controller talking through the given transport.
+    pub fn new<T: Transport + 'static>(transport: T, product: u16) -> Result<Controller> {
+        let descriptor = Descriptor {
+            bus: 0,
+            address: 0,
             product,
-            address,
-            index,
+            serial: None,
         };
 
+        let mut controller = Controller::attach(Box::new(transport), descriptor, 0);
+
         controller.reset()?;
         // controller.led().off()?;
         // controller.sensors().off()?;
@@ -171,14 +139,49 @@ impl Controller {
     //     Ok(controller)
     // }
 
+    /// Record the traffic with the controller to the writer from now on, see
+    /// `transport::Recorder`.
+    pub fn record<W: Write + Send + 'static>(mut self, writer: W) -> Result<Controller> {
+        self.transport = Box::new(Recorder::new(self.transport, writer)?);
+
+        Ok(self)
+    }
+
+    /// Wrap the transport without sending anything to the device.
+    pub(crate) fn attach(transport: Box<dyn Transport>, descriptor: Descriptor, slot: u8) -> Controller {
+        Controller {
+            transport,
+            packet: [0u8; PACKET_MAX_SIZE],
+            input: [0u8; PACKET_MAX_SIZE],
+            settings: Default::default(),
+            tracker: Tracker::new(),
+            battery: None,
+
+            descriptor,
+            slot,
+        }
+    }
+
+    /// The device the controller is reached through.
+    ///
+    /// Controllers not opened through a `Manager` have a zero bus and address.
+    pub fn descriptor(&self) -> &Descriptor {
+        &self.descriptor
+    }
+
+    /// The slot of the controller on the dongle, always `0` when wired.
+    pub fn slot(&self) -> u8 {
+        self.slot
+    }
+
     /// Check if the controller is remote.
     pub fn is_remote(&self) -> bool {
-        self.product == 0x1142
+        self.descriptor.is_wireless()
     }
 
     /// Check if the controller is wired.
     pub fn is_wired(&self) -> bool {
-        self.product == 0x1102
+        self.descriptor.is_wired()
     }
 
     /// Check if the controller is connected.
@@ -194,6 +197,30 @@ impl Controller {
         }
     }
 
+    /// The battery state from the last status report, `None` until one is
+    /// read through `state`.
+    ///
+    /// Wireless controllers send a status report every few seconds while
+    /// idle, wired controllers have no battery.
+    pub fn battery(&self) -> Result<Option<Battery>> {
+        if self.is_wired() {
+            return Err(Error::Unsupported("battery status of a wired controller"));
+        }
+
+        Ok(self.battery)
+    }
+
+    /// Fail on unknown reports and buttons instead of returning them as
+    /// `State::Unknown` and as unknown bits in `Button`, off by default.
+    pub fn strict(&mut self, value: bool) {
+        self.settings.strict = value;
+    }
+
+    /// Check if unknown reports and buttons are treated as errors.
+    pub fn is_strict(&self) -> bool {
+        self.settings.strict
+    }
+
     #[doc(hidden)]
     pub fn settings(&mut self) -> &mut Settings {
         &mut self.settings
@@ -213,7 +240,7 @@ impl Controller {
             self.control_with(0x87, 0x15, |mut buf| {
                 buf.write_u8(0x32)?;
                 buf.write_u16::<LittleEndian>(timeout)?;
-                buf.write(&[0x18, 0x00, 0x00, 0x31, 0x02, 0x00, 0x08, 0x07, 0x00, 0x07, 0x07, 0x00, 0x30])?;
+                buf.write_all(&[0x18, 0x00, 0x00, 0x31, 0x02, 0x00, 0x08, 0x07, 0x00, 0x07, 0x07, 0x00, 0x30])?;
                 buf.write_u8(0x14)?;
                 buf.write(&[0x00, 0x2e])
             })?;
@@ -221,12 +248,18 @@ impl Controller {
             self.control_with(0x87, 0x15, |mut buf| {
                 buf.write_u8(0x32)?;
                 buf.write_u16::<LittleEndian>(timeout)?;
-                buf.write(&[0x18, 0x00, 0x00, 0x31, 0x02, 0x00, 0x08, 0x07, 0x00, 0x07, 0x07, 0x00, 0x30])?;
+                buf.write_all(&[0x18, 0x00, 0x00, 0x31, 0x02, 0x00, 0x08, 0x07, 0x00, 0x07, 0x07, 0x00, 0x30])?;
                 buf.write_u8(0x00)?;
                 buf.write(&[0x00, 0x2e])
             })?;
         }
 
+        if let Some(level) = self.settings.led {
+            self.control_with(0x87, 0x03, |mut buf| {
+                buf.write(&[0x2d, level])
+            })?;
+        }
+
         Ok(())
     }
 
@@ -244,7 +277,7 @@ impl Controller {
         self.packet[PACKET_START_IND + 1] = size;
 
         func(Cursor::new(&mut self.packet[PACKET_START_IND + 2..]))?;
-        self.handle.write_control(0x21, 0x09, 0x0300, self.index, &self.packet[..], Duration::from_secs(0))?;
+        self.transport.send_feature(&self.packet[..])?;
 
         Ok(())
     }
@@ -294,17 +327,15 @@ impl Controller {
 
         let mut limit = LIMIT;
         loop {
-            request!(limit, self.handle.write_control(0x21, 0x09, 0x0300, self.index,
-				&self.packet[..], Duration::from_secs(0)));
+            request!(limit, self.transport.send_feature(&self.packet[..]));
 
-            request!(limit, self.handle.read_control(0xa1, 0x01, 0x0300,
-				self.index, &mut self.packet[..], Duration::from_secs(0)));
+            request!(limit, self.transport.get_feature(&mut self.packet[..]));
 
             if self.packet[PACKET_START_IND] == id && self.packet[PACKET_START_IND + 1] != 0 {
                 break;
             }
 
-            request!(limit, Err(rusb::Error::NotSupported));
+            request!(limit, Err(Error::RetryExhausted(id)));
         }
 
         Ok(&self.packet[PACKET_START_IND + 2..(self.packet[PACKET_START_IND + 1] + 2) as usize])
@@ -364,32 +395,32 @@ impl Controller {
     // }
 
     /// Get the lizard manager.
-    pub fn lizard(&mut self) -> Lizard {
+    pub fn lizard(&mut self) -> Lizard<'_> {
         Lizard::new(self)
     }
 
     /// Get the LED manager.
-    pub fn led(&mut self) -> Led {
+    pub fn led(&mut self) -> Led<'_> {
         Led::new(self)
     }
 
     /// Get the feedback builder.
-    pub fn feedback(&mut self) -> Feedback {
+    pub fn feedback(&mut self) -> Feedback<'_> {
         Feedback::new(self)
     }
 
     /// Get the sensor manager.
-    pub fn sensors(&mut self) -> Sensors {
+    pub fn sensors(&mut self) -> Sensors<'_> {
         Sensors::new(self)
     }
 
     /// Get the calibration manager.
-    pub fn calibrate(&mut self) -> Calibrate {
+    pub fn calibrate(&mut self) -> Calibrate<'_> {
         Calibrate::new(self)
     }
 
     /// Get the sound player.
-    pub fn sound(&mut self) -> Sound {
+    pub fn sound(&mut self) -> Sound<'_> {
         Sound::new(self)
     }
 
@@ -434,30 +465,29 @@ impl Controller {
         })
     }
 
-    #[cfg(feature = "debug_mode")]
+    /// Read a report, returning its type and payload.
     #[inline]
-    pub fn receive(&mut self, timeout: Duration) -> Result<(u8, &[u8], &[u8])> {
-        self.handle.read_interrupt(self.address, &mut self.debug_packet, timeout)?;
+    pub fn receive(&mut self, timeout: Duration) -> Result<(u8, &[u8])> {
+        let size = self.transport.read_input(&mut self.input, timeout)?;
+
+        if size != PACKET_MAX_SIZE {
+            return Err(Error::ShortReport {
+                expected: PACKET_MAX_SIZE,
+                actual: size,
+            });
+        }
+
+        let end = (self.input[PACKET_START_IND + 3] as usize + PACKET_START_IND + 4).min(PACKET_MAX_SIZE);
 
         Ok((
-            self.debug_packet[PACKET_START_IND + 2],
-            &self.debug_packet[PACKET_START_IND + 4..(self.debug_packet[PACKET_START_IND + 3] as usize + PACKET_START_IND + 4)],
-            &self.debug_packet
+            self.input[PACKET_START_IND + 2],
+            &self.input[PACKET_START_IND + 4..end],
         ))
     }
 
-    #[cfg(not(feature = "debug_mode"))]
-    #[inline]
-    pub fn receive(&mut self, timeout: Duration) -> Result<(u8, &[u8], &[u8])> {
-        if self.handle.read_interrupt(self.address, &mut self.packet, timeout)? != PACKET_MAX_SIZE {
-            bail!(rusb::Error::InvalidParam);
-        }
-
-        Ok((
-            self.packet[PACKET_START_IND + 2],
-            &self.packet[PACKET_START_IND + 4..(self.packet[PACKET_START_IND + 3] as usize + PACKET_START_IND + 4)],
-            &self.packet
-        ))
+    /// The full 64 bytes of the last report read.
+    pub fn raw_report(&self) -> &[u8; 64] {
+        self.input[PACKET_START_IND..PACKET_START_IND + 64].try_into().unwrap()
     }
 
     // pub fn receive(&mut self, timeout: Duration) -> Result<(u8, &[u8])> {
@@ -478,35 +508,31 @@ impl Controller {
     // }
 
     /// Get the current state of the controller.
-    #[cfg(feature = "debug_mode")]
     #[inline]
-    pub fn state(&mut self, timeout: Duration) -> Result<(State, Vec<u8>)> {
-        let (id, buffer, full_buffer) = self.receive(timeout)?;
-        let buf_export = full_buffer.to_vec();
-
-        let state = State::parse(id, Cursor::new(buffer))?;
+    pub fn state(&mut self, timeout: Duration) -> Result<State> {
+        let (id, buffer) = self.receive(timeout)?;
+        let state = report::parse_payload(id, buffer)?;
 
-        if let State::Power(true) = state {
-            self.reset()?;
+        if self.settings.strict {
+            state.validate()?;
         }
 
-        Ok((state, buf_export))
-    }
-
-    #[cfg(not(feature = "debug_mode"))]
-    #[inline]
-    pub fn state(&mut self, timeout: Duration) -> Result<(State, bool)> {
-        let (id, buffer, _) = self.receive(timeout)?;
+        let state = self.tracker.update(state);
 
-        // doesn't work when touching right pad at the same time
-        let is_left_pad = buffer[6] == 8;
+        match state {
+            State::Power(true) => self.reset()?,
+            State::Status { battery, .. } => self.battery = Some(battery),
+            _ => (),
+        }
 
-        let state = State::parse(id, Cursor::new(buffer))?;
+        Ok(state)
+    }
 
-        if let State::Power(true) = state {
-            self.reset()?;
-        }
+    /// Get the current state of the controller along with the full report it
+    /// was parsed from.
+    pub fn state_with_raw(&mut self, timeout: Duration) -> Result<(State, [u8; 64])> {
+        let state = self.state(timeout)?;
 
-        Ok((state, is_left_pad))
+        Ok((state, *self.raw_report()))
     }
 }
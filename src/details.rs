@@ -1,7 +1,7 @@
 use std::io::{Read, Seek, SeekFrom};
 use std::time::{UNIX_EPOCH, Duration, SystemTime};
 use byteorder::{ReadBytesExt, LittleEndian, BigEndian};
-use color_eyre::{Result};
+use crate::{Result};
 
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub struct Details {
@@ -69,7 +69,7 @@ impl Serial {
         buffer.read_u8()?;
 
         let mut serial = [0u8; 10];
-        buffer.read(&mut serial[..])?;
+        buffer.read_exact(&mut serial[..])?;
 
         Ok(serial)
     }
@@ -87,7 +87,7 @@ impl Receiver {
         buffer.seek(SeekFrom::Current(10))?;
 
         let mut serial = [0u8; 10];
-        buffer.read(&mut serial[..])?;
+        buffer.read_exact(&mut serial[..])?;
 
         Ok(Receiver {
             firmware: UNIX_EPOCH + Duration::from_secs(firmware as u64),
@@ -0,0 +1,157 @@
+use core::{error, fmt};
+#[cfg(feature = "std")]
+use std::io;
+
+/// Result type used throughout the library.
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// Errors returned by the library.
+///
+/// Some variants only exist with the features needing them, so the enum is
+/// non exhaustive.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The USB transport failed.
+    #[cfg(feature = "std")]
+    Usb(rusb::Error),
+
+    /// Reading or writing a packet failed.
+    #[cfg(feature = "std")]
+    Io(io::Error),
+
+    /// The device has been unplugged or is otherwise gone.
+    Disconnected,
+
+    /// The operation timed out.
+    Timeout,
+
+    /// No matching controller has been found.
+    NotFound,
+
+    /// A report was shorter than expected.
+    ShortReport {
+        /// The expected size.
+        expected: usize,
+
+        /// The size that was actually received.
+        actual: usize,
+    },
+
+    /// A report contained invalid data.
+    Malformed(&'static str),
+
+    /// A report with an unknown id has been received.
+    UnknownReport(u8),
+
+    /// A report contained button bits that are not known.
+    UnknownButtons(u32),
+
+    /// A request with the given id got no answer within the retry limit.
+    RetryExhausted(u8),
+
+    /// The operation is not supported on this product.
+    Unsupported(&'static str),
+
+    /// A mapping profile could not be parsed or refers to unknown layers.
+    #[cfg(feature = "mapping")]
+    Profile(String),
+}
+
+impl Error {
+    /// Check if the error means the device can't be used anymore and has to
+    /// be opened again.
+    ///
+    /// libusb reports an unplugged device as an I/O error on pending
+    /// transfers. Endpoint stalls and other errors can be transient and
+    /// aren't treated as disconnections.
+    pub fn is_disconnected(&self) -> bool {
+        match *self {
+            Error::Disconnected => true,
+
+            #[cfg(feature = "std")]
+            Error::Usb(rusb::Error::NoDevice) | Error::Usb(rusb::Error::Io) => true,
+
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            #[cfg(feature = "std")]
+            Error::Usb(ref e) =>
+                write!(f, "USB error: {}", e),
+
+            #[cfg(feature = "std")]
+            Error::Io(ref e) =>
+                write!(f, "I/O error: {}", e),
+
+            Error::Disconnected =>
+                f.write_str("device disconnected"),
+
+            Error::Timeout =>
+                f.write_str("operation timed out"),
+
+            Error::NotFound =>
+                f.write_str("no controller found"),
+
+            Error::ShortReport { expected, actual } =>
+                write!(f, "short report: expected {} bytes, got {}", expected, actual),
+
+            Error::Malformed(reason) =>
+                write!(f, "malformed report: {}", reason),
+
+            Error::UnknownReport(id) =>
+                write!(f, "unknown report id {:#04x}", id),
+
+            Error::UnknownButtons(bits) =>
+                write!(f, "unknown button bits {:#08x}", bits),
+
+            Error::RetryExhausted(id) =>
+                write!(f, "no answer to request {:#04x}", id),
+
+            Error::Unsupported(what) =>
+                write!(f, "unsupported on this product: {}", what),
+
+            #[cfg(feature = "mapping")]
+            Error::Profile(ref reason) =>
+                write!(f, "invalid profile: {}", reason),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            #[cfg(feature = "std")]
+            Error::Usb(ref e) => Some(e),
+            #[cfg(feature = "std")]
+            Error::Io(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<rusb::Error> for Error {
+    fn from(value: rusb::Error) -> Self {
+        match value {
+            rusb::Error::NoDevice => Error::Disconnected,
+            rusb::Error::Timeout => Error::Timeout,
+            e => Error::Usb(e),
+        }
+    }
+}
+
+/// Running out of data while decoding is reported as a malformed report.
+#[cfg(feature = "std")]
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        match value.kind() {
+            io::ErrorKind::UnexpectedEof => Error::Malformed("truncated report"),
+            _ => Error::Io(value),
+        }
+    }
+}
@@ -1,6 +1,22 @@
 use byteorder::{WriteBytesExt, LittleEndian};
 use crate::{Controller};
-use color_eyre::{Result};
+use crate::{Result};
+
+/// A haptic pulse to send later on, see `Feedback::pulse`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Pulse {
+    /// Send it on the left pad instead of the right one.
+    pub left: bool,
+
+    /// The amplitude of the feedback.
+    pub amplitude: u16,
+
+    /// The period of the feedback.
+    pub period: u16,
+
+    /// The number of feedbacks to send.
+    pub count: u16,
+}
 
 /// Controller feedback builder.
 pub struct Feedback<'a> {
@@ -53,6 +69,15 @@ impl<'a> Feedback<'a> {
         self
     }
 
+    /// Use the side, amplitude, period and count of the pulse.
+    pub fn pulse(mut self, pulse: Pulse) -> Self {
+        self.side = pulse.left as u8;
+        self.amplitude = pulse.amplitude;
+        self.period = pulse.period;
+        self.count = pulse.count;
+        self
+    }
+
     /// Send the built feedback.
     pub fn send(self) -> Result<()> {
         let side = self.side;
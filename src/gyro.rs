@@ -0,0 +1,305 @@
+//! Gyro aiming.
+//!
+//! The angular velocity reported by the controller is turned into mouse
+//! motion or into a right stick, like Steam Input does. Small motion can be
+//! smoothed to hide the shaking of the hands, and tightened to make aiming
+//! steadier.
+//!
+//! The angular velocity is taken from the `gyro` angles of input states.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use crate::{Angles, Button, State, Result};
+use crate::mouse::Acceleration;
+use crate::motion::GYRO;
+use crate::uinput::{Event, Sink};
+use crate::uinput::code::*;
+#[cfg(target_os = "linux")]
+use crate::uinput::{Absolute, Device};
+
+/// Default pixels per degree.
+const SENSITIVITY: f32 = 10.0;
+
+/// Samples averaged by the smoothing.
+const WINDOW: usize = 8;
+
+/// Where the aim goes.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Output {
+    /// Relative mouse motion, the sensitivity is in pixels per degree.
+    Mouse,
+
+    /// The right stick of a gamepad, the sensitivity is the deflection per
+    /// degree per second, `1.0 / 360.0` being fully deflected when turning
+    /// at a revolution per second.
+    Stick,
+}
+
+/// When the aim is active.
+///
+/// Disabling it while holding a button is what lets the controller be moved
+/// back to a comfortable position without moving the aim, like lifting a
+/// mouse, called ratcheting.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Activation {
+    /// All the time.
+    Always,
+
+    /// While any of the buttons is held.
+    Hold(Button),
+
+    /// Unless any of the buttons is held.
+    Release(Button),
+
+    /// Each press of any of the buttons switches it on or off.
+    Toggle(Button),
+}
+
+/// Aiming with the gyro of the controller.
+///
+/// The sensors must be enabled, the yaw turns the aim horizontally and the
+/// pitch vertically.
+pub struct Gyro<S: Sink> {
+    sink: S,
+    output: Output,
+    sensitivity: (f32, f32),
+    acceleration: Acceleration,
+    smoothing: Option<f32>,
+    tightening: Option<f32>,
+    activation: Activation,
+
+    enabled: bool,
+    pressed: bool,
+    samples: VecDeque<(f32, f32)>,
+    remainder: (f32, f32),
+    stick: (i32, i32),
+    updated: Option<Instant>,
+    events: Vec<Event>,
+}
+
+#[cfg(target_os = "linux")]
+impl Gyro<Device> {
+    /// Create a uinput device for the given output.
+    pub fn create(name: &str, output: Output) -> Result<Gyro<Device>> {
+        let device = match output {
+            Output::Mouse =>
+                Device::new(name, &[BTN_LEFT, BTN_RIGHT, BTN_MIDDLE], &[REL_X, REL_Y], &[])?,
+
+            Output::Stick => {
+                let stick = |code| Absolute { code, minimum: i16::MIN as i32, maximum: i16::MAX as i32, fuzz: 0, flat: 0 };
+                Device::new(name, &[BTN_SOUTH], &[], &[stick(ABS_RX), stick(ABS_RY)])?
+            }
+        };
+
+        Ok(Gyro::new(device, output))
+    }
+}
+
+impl<S: Sink> Gyro<S> {
+    /// Create an aim emitting to the given sink.
+    pub fn new(sink: S, output: Output) -> Gyro<S> {
+        Gyro {
+            sink,
+            output,
+            sensitivity: match output {
+                Output::Mouse => (SENSITIVITY, SENSITIVITY),
+                Output::Stick => (1.0 / 360.0, 1.0 / 360.0),
+            },
+            acceleration: Acceleration::Flat,
+            smoothing: None,
+            tightening: None,
+            activation: Activation::Always,
+
+            enabled: false,
+            pressed: false,
+            samples: VecDeque::with_capacity(WINDOW),
+            remainder: (0.0, 0.0),
+            stick: (0, 0),
+            updated: None,
+            events: Vec::new(),
+        }
+    }
+
+    /// The sink events are emitted to.
+    pub fn sink(&mut self) -> &mut S {
+        &mut self.sink
+    }
+
+    /// Change the horizontal and vertical sensitivity, negative values invert
+    /// the axis.
+    pub fn sensitivity(&mut self, horizontal: f32, vertical: f32) {
+        self.sensitivity = (horizontal, vertical);
+    }
+
+    /// Change the sensitivity curve, the speed is in degrees per
+    /// millisecond.
+    pub fn acceleration(&mut self, value: Acceleration) {
+        self.acceleration = value;
+    }
+
+    /// Average the motion slower than the given degrees per second, `None`
+    /// disables smoothing.
+    pub fn smoothing(&mut self, threshold: Option<f32>) {
+        self.smoothing = threshold;
+        self.samples.clear();
+    }
+
+    /// Scale down the motion slower than the given degrees per second, `None`
+    /// disables tightening.
+    pub fn tightening(&mut self, threshold: Option<f32>) {
+        self.tightening = threshold;
+    }
+
+    /// Change when the aim is active.
+    pub fn activation(&mut self, value: Activation) {
+        self.activation = value;
+        self.enabled = false;
+        self.pressed = false;
+    }
+
+    /// Check if the aim is active.
+    pub fn is_active(&self) -> bool {
+        match self.activation {
+            Activation::Always => true,
+            Activation::Hold(_) => self.pressed,
+            Activation::Release(_) => !self.pressed,
+            Activation::Toggle(_) => self.enabled,
+        }
+    }
+
+    /// Handle a state received at the given time, other states than
+    /// `State::Input` are ignored.
+    pub fn update(&mut self, state: &State, at: Instant) -> Result<()> {
+        let State::Input { buttons, gyro, .. } = *state else {
+            return Ok(());
+        };
+
+        let elapsed = self.updated.map_or(Duration::ZERO, |updated| at.saturating_duration_since(updated));
+        self.updated = Some(at);
+
+        let pressed = match self.activation {
+            Activation::Always => false,
+            Activation::Hold(button) | Activation::Release(button) | Activation::Toggle(button) =>
+                buttons.intersects(button),
+        };
+
+        if pressed && !self.pressed {
+            self.enabled = !self.enabled;
+        }
+
+        self.pressed = pressed;
+        self.events.clear();
+
+        let rate = if self.is_active() {
+            self.rate(gyro)
+        } else {
+            // Start afresh once active again.
+            self.samples.clear();
+            self.remainder = (0.0, 0.0);
+
+            (0.0, 0.0)
+        };
+
+        match self.output {
+            Output::Mouse =>
+                self.mouse(rate, elapsed),
+
+            Output::Stick =>
+                self.stick(rate),
+        }
+
+        if !self.events.is_empty() {
+            self.events.push(Event::sync());
+            self.sink.emit(&self.events)?;
+        }
+
+        Ok(())
+    }
+
+    /// The processed angular velocity in degrees per second, right and down
+    /// being positive.
+    fn rate(&mut self, angles: Angles) -> (f32, f32) {
+        let mut rate = (-angles.yaw as f32 * GYRO, -angles.pitch as f32 * GYRO);
+
+        if let Some(threshold) = self.smoothing {
+            rate = self.smooth(rate, threshold);
+        }
+
+        if let Some(threshold) = self.tightening {
+            let speed = rate.0.hypot(rate.1);
+
+            if speed < threshold {
+                let scale = speed / threshold;
+                rate = (rate.0 * scale, rate.1 * scale);
+            }
+        }
+
+        let gain = self.acceleration.gain(rate.0.hypot(rate.1) / 1000.0);
+
+        (rate.0 * gain, rate.1 * gain)
+    }
+
+    /// Blend the rate with its average, fully below half the threshold and
+    /// not at all above it.
+    fn smooth(&mut self, rate: (f32, f32), threshold: f32) -> (f32, f32) {
+        if self.samples.len() == WINDOW {
+            self.samples.pop_front();
+        }
+
+        self.samples.push_back(rate);
+
+        let count = self.samples.len() as f32;
+        let average = self.samples.iter().fold((0.0, 0.0), |sum, sample| (sum.0 + sample.0, sum.1 + sample.1));
+        let average = (average.0 / count, average.1 / count);
+
+        let half = threshold / 2.0;
+        let direct = if half > 0.0 {
+            ((rate.0.hypot(rate.1) - half) / half).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+
+        (
+            rate.0 * direct + average.0 * (1.0 - direct),
+            rate.1 * direct + average.1 * (1.0 - direct),
+        )
+    }
+
+    fn mouse(&mut self, rate: (f32, f32), elapsed: Duration) {
+        let seconds = elapsed.as_secs_f32();
+
+        self.remainder.0 += rate.0 * seconds * self.sensitivity.0;
+        self.remainder.1 += rate.1 * seconds * self.sensitivity.1;
+
+        let x = self.remainder.0.trunc();
+        let y = self.remainder.1.trunc();
+        self.remainder.0 -= x;
+        self.remainder.1 -= y;
+
+        if x != 0.0 {
+            self.events.push(Event::relative(REL_X, x as i32));
+        }
+
+        if y != 0.0 {
+            self.events.push(Event::relative(REL_Y, y as i32));
+        }
+    }
+
+    fn stick(&mut self, rate: (f32, f32)) {
+        let deflect = |rate: f32, sensitivity: f32| {
+            ((rate * sensitivity).clamp(-1.0, 1.0) * i16::MAX as f32).round() as i32
+        };
+
+        let stick = (deflect(rate.0, self.sensitivity.0), deflect(rate.1, self.sensitivity.1));
+
+        if stick.0 != self.stick.0 {
+            self.events.push(Event::absolute(ABS_RX, stick.0));
+        }
+
+        if stick.1 != self.stick.1 {
+            self.events.push(Event::absolute(ABS_RY, stick.1));
+        }
+
+        self.stick = stick;
+    }
+}
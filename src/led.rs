@@ -1,13 +1,13 @@
 use std::io::Write;
 use crate::{Controller};
-use color_eyre::{Result};
+use crate::{Result};
 
 /// Controller led management.
 pub struct Led<'a> {
     controller: &'a mut Controller,
 }
 
-impl<'a, 'b> Led<'a> {
+impl<'a> Led<'a> {
     #[doc(hidden)]
     pub fn new(controller: &'a mut Controller) -> Led<'a> {
         Led {
@@ -15,11 +15,15 @@ impl<'a, 'b> Led<'a> {
         }
     }
 
-    /// Change the LED luminosity.
+    /// Change the LED luminosity, restored on reconnection.
     pub fn level(self, value: u8) -> Result<()> {
         self.controller.control_with(0x87, 0x03, |mut buf| {
             buf.write(&[0x2d, value])
-        })
+        })?;
+
+        self.controller.settings().led = Some(value);
+
+        Ok(())
     }
 
     /// Turn the LED off.
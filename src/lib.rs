@@ -1,40 +1,101 @@
 //! Steam controller handling library.
+//!
+//! Without the default `std` feature only the report parsing is available,
+//! see the `report` module.
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "std")]
 const VENDOR_ID: u16 = 0x28de;
+#[cfg(feature = "std")]
 const PRODUCT_ID: [u16; 2] = [0x1102, 0x1142];
-const ENDPOINT: [u8; 2] = [3, 2];
+/// First controller interface for each product, the dongle has one per slot.
+#[cfg(feature = "std")]
 const INDEX: [u16; 2] = [2, 1];
 
+mod error;
+
+pub use error::{Error, Result};
+
+#[cfg(feature = "std")]
 mod manager;
 
-pub use manager::Manager;
+#[cfg(feature = "std")]
+pub use manager::{Manager, Backend, Descriptor, Selector};
+
+#[cfg(feature = "std")]
+pub mod monitor;
+
+#[cfg(feature = "std")]
+pub use monitor::Monitor;
+
+#[cfg(feature = "std")]
+pub mod reconnect;
+
+#[cfg(feature = "std")]
+pub use reconnect::Reconnecting;
+
+#[cfg(feature = "std")]
+mod worker;
 
+#[cfg(feature = "std")]
+pub mod threaded;
+
+#[cfg(feature = "std")]
+pub use threaded::Threaded;
+
+#[cfg(feature = "async")]
+mod asynchronous;
+
+#[cfg(feature = "async")]
+pub use asynchronous::AsyncController;
+
+#[cfg(feature = "std")]
 mod controller;
 
+#[cfg(feature = "std")]
 pub use controller::Controller;
 
+#[cfg(feature = "std")]
+pub mod transport;
+
+#[cfg(feature = "std")]
+pub use transport::Transport;
+
+#[cfg(feature = "std")]
 mod feedback;
 
-pub use feedback::Feedback;
+#[cfg(feature = "std")]
+pub use feedback::{Feedback, Pulse};
 
+#[cfg(feature = "std")]
 mod sensors;
 
+#[cfg(feature = "std")]
 pub use sensors::Sensors;
 
+#[cfg(feature = "std")]
 mod led;
 
+#[cfg(feature = "std")]
 pub use led::Led;
 
+#[cfg(feature = "std")]
 pub mod sound;
 
+#[cfg(feature = "std")]
 pub use sound::Sound;
 
+#[cfg(feature = "std")]
 mod calibrate;
 
+#[cfg(feature = "std")]
 pub use calibrate::Calibrate;
 
+#[cfg(feature = "std")]
 mod lizard;
 
+#[cfg(feature = "std")]
 pub use lizard::Lizard;
 
 pub mod button;
@@ -43,8 +104,63 @@ pub use button::Button;
 
 mod state;
 
-pub use state::{State, Axis, Trigger, Pad, Angles};
+pub use state::{State, Axis, Trigger, Pad, Side, Angles, Quaternion, Battery, Tracker};
+
+pub mod report;
+
+#[cfg(feature = "std")]
+pub mod uinput;
+
+#[cfg(feature = "std")]
+pub mod mouse;
+
+#[cfg(feature = "std")]
+pub use mouse::Mouse;
+
+#[cfg(feature = "std")]
+pub mod menu;
+
+#[cfg(feature = "std")]
+pub use menu::Menu;
+
+#[cfg(feature = "std")]
+pub mod scroll;
+
+#[cfg(feature = "std")]
+pub use scroll::Scroll;
+
+#[cfg(feature = "std")]
+pub mod process;
+
+#[cfg(feature = "std")]
+pub use process::{Processor, Pull};
+
+#[cfg(feature = "std")]
+pub mod gyro;
+
+#[cfg(feature = "std")]
+pub use gyro::Gyro;
+
+#[cfg(feature = "std")]
+pub mod motion;
+
+#[cfg(feature = "std")]
+pub use motion::Fusion;
+
+#[cfg(feature = "std")]
+pub mod calibration;
+
+#[cfg(feature = "std")]
+pub use calibration::Calibration;
+
+#[cfg(feature = "mapping")]
+pub mod mapping;
+
+#[cfg(feature = "mapping")]
+pub use mapping::Mapper;
 
+#[cfg(feature = "std")]
 pub mod details;
 
+#[cfg(feature = "std")]
 pub use details::Details;
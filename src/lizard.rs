@@ -1,12 +1,12 @@
 use crate::{Controller};
-use color_eyre::{Result};
+use crate::{Result};
 
 /// Controller led management.
 pub struct Lizard<'a> {
     controller: &'a mut Controller,
 }
 
-impl<'a, 'b> Lizard<'a> {
+impl<'a> Lizard<'a> {
     #[doc(hidden)]
     pub fn new(controller: &'a mut Controller) -> Lizard<'a> {
         Lizard {
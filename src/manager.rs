@@ -1,42 +1,374 @@
-use crate::{Controller};
-use crate::{VENDOR_ID, PRODUCT_ID, ENDPOINT, INDEX};
-use color_eyre::{Result};
-use color_eyre::eyre::bail;
+use crate::{Controller, Monitor};
+use crate::transport::{Transport, Usb};
+#[cfg(target_os = "linux")]
+use crate::transport::{hidraw, Hidraw};
+use crate::{VENDOR_ID, PRODUCT_ID, INDEX};
+use crate::{Error, Result};
 use rusb::UsbContext;
 
 /// Controller manager.
 pub struct Manager {
-    usb: rusb::Context,
+    usb: Option<rusb::Context>,
+}
+
+/// How the controllers are reached.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Backend {
+    /// Through libusb, detaching the kernel drivers and claiming the
+    /// interfaces.
+    Usb,
+
+    /// Through the Linux `/dev/hidraw*` nodes, leaving the kernel drivers
+    /// attached.
+    #[cfg(target_os = "linux")]
+    Hidraw,
+}
+
+/// A controller device found on the bus.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Descriptor {
+    /// The bus number.
+    pub bus: u8,
+
+    /// The address on the bus.
+    pub address: u8,
+
+    /// The product id, `0x1102` for wired controllers and `0x1142` for
+    /// wireless dongles.
+    pub product: u16,
+
+    /// The serial number, if it was read and could be.
+    pub serial: Option<String>,
+}
+
+impl Descriptor {
+    /// Check if the device is a wired controller.
+    pub fn is_wired(&self) -> bool {
+        self.product == 0x1102
+    }
+
+    /// Check if the device is a wireless dongle.
+    pub fn is_wireless(&self) -> bool {
+        self.product == 0x1142
+    }
+}
+
+/// How to pick a device among the ones found.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Selector {
+    /// The device at the given bus and address.
+    Address {
+        /// The bus number.
+        bus: u8,
+
+        /// The address on the bus.
+        address: u8,
+    },
+
+    /// The device with the given serial number.
+    Serial(String),
+
+    /// The nth device in the order returned by `Manager::list`.
+    Index(usize),
+}
+
+impl Selector {
+    fn matches(&self, index: usize, descriptor: &Descriptor) -> bool {
+        match *self {
+            Selector::Address { bus, address } =>
+                descriptor.bus == bus && descriptor.address == address,
+
+            Selector::Serial(ref serial) =>
+                descriptor.serial.as_ref() == Some(serial),
+
+            Selector::Index(value) =>
+                index == value,
+        }
+    }
+}
+
+pub(crate) fn scan(usb: &rusb::Context) -> Result<Vec<(rusb::Device<rusb::Context>, Descriptor)>> {
+    let mut devices = Vec::new();
+
+    for device in usb.devices()?.iter() {
+        let descriptor = device.device_descriptor()?;
+
+        if descriptor.vendor_id() != VENDOR_ID || !PRODUCT_ID.contains(&descriptor.product_id()) {
+            continue;
+        }
+
+        devices.push((device.clone(), Descriptor {
+            bus: device.bus_number(),
+            address: device.address(),
+            product: descriptor.product_id(),
+            serial: None,
+        }));
+    }
+
+    devices.sort_by_key(|(_, d)| (d.bus, d.address));
+
+    Ok(devices)
+}
+
+fn first_interface(product: u16) -> Option<u16> {
+    PRODUCT_ID.iter().zip(INDEX.iter())
+        .find(|&(&id, _)| id == product)
+        .map(|(_, &index)| index)
+}
+
+/// Read the serial number of the device, if possible.
+pub(crate) fn serial(device: &rusb::Device<rusb::Context>) -> Option<String> {
+    let descriptor = device.device_descriptor().ok()?;
+    let handle = device.open().ok()?;
+
+    handle.read_serial_number_string_ascii(&descriptor).ok()
+}
+
+/// A device found by one of the backends.
+pub(crate) enum Device {
+    Usb(rusb::Device<rusb::Context>),
+
+    #[cfg(target_os = "linux")]
+    Hidraw(hidraw::Nodes),
+}
+
+impl Device {
+    /// Find the devices, reading the serial numbers only if asked to as it
+    /// requires opening the devices with libusb.
+    pub(crate) fn scan(usb: Option<&rusb::Context>, serials: bool) -> Result<Vec<(Device, Descriptor)>> {
+        match usb {
+            Some(usb) => {
+                let mut devices = scan(usb)?;
+
+                if serials {
+                    for (device, descriptor) in &mut devices {
+                        descriptor.serial = serial(device);
+                    }
+                }
+
+                Ok(devices.into_iter().map(|(device, descriptor)| (Device::Usb(device), descriptor)).collect())
+            }
+
+            #[cfg(target_os = "linux")]
+            None => {
+                Ok(hidraw::scan()?.into_iter().map(|(nodes, descriptor)| (Device::Hidraw(nodes), descriptor)).collect())
+            }
+
+            #[cfg(not(target_os = "linux"))]
+            None => {
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    /// Read the serial number of the device, if it's not known already.
+    pub(crate) fn identify(&self, descriptor: &mut Descriptor) {
+        if let Device::Usb(ref device) = *self {
+            descriptor.serial = serial(device);
+        }
+    }
+
+    /// Open a transport for every controller interface of the device.
+    fn open(self, product: u16) -> Result<Vec<Box<dyn Transport>>> {
+        let first = first_interface(product).ok_or(Error::NotFound)?;
+
+        match self {
+            Device::Usb(device) => {
+                Ok(Usb::open(device, first)?.into_iter()
+                    .map(|transport| Box::new(transport) as Box<dyn Transport>)
+                    .collect())
+            }
+
+            #[cfg(target_os = "linux")]
+            Device::Hidraw(nodes) => {
+                let transports = nodes.into_iter()
+                    .filter(|&(number, _)| number >= first)
+                    .map(|(_, path)| Ok(Box::new(Hidraw::open(path)?) as Box<dyn Transport>))
+                    .collect::<Result<Vec<_>>>()?;
+
+                if transports.is_empty() {
+                    return Err(Error::NotFound);
+                }
+
+                Ok(transports)
+            }
+        }
+    }
 }
 
 impl Manager {
-    /// Create a new controller manager.
+    /// Create a new controller manager going through libusb.
     pub fn new() -> Result<Manager> {
+        Manager::with_backend(Backend::Usb)
+    }
+
+    /// Create a new controller manager going through the given backend.
+    pub fn with_backend(backend: Backend) -> Result<Manager> {
         Ok(Manager {
-            usb: rusb::Context::new()?,
+            usb: match backend {
+                Backend::Usb => Some(rusb::Context::new()?),
+
+                #[cfg(target_os = "linux")]
+                Backend::Hidraw => None,
+            },
         })
     }
 
+    /// The backend in use.
+    pub fn backend(&self) -> Backend {
+        match self.usb {
+            Some(_) => Backend::Usb,
+
+            #[cfg(target_os = "linux")]
+            None => Backend::Hidraw,
+
+            #[cfg(not(target_os = "linux"))]
+            None => unreachable!(),
+        }
+    }
+
+    fn devices(&self, serials: bool) -> Result<Vec<(Device, Descriptor)>> {
+        Device::scan(self.usb.as_ref(), serials)
+    }
+
+    /// List the controller devices currently plugged in.
+    ///
+    /// With libusb the serial numbers are left out, as reading them requires
+    /// opening every device, see `list_serials`.
+    pub fn list(&self) -> Result<Vec<Descriptor>> {
+        Ok(self.devices(false)?.into_iter().map(|(_, descriptor)| descriptor).collect())
+    }
+
+    /// List the controller devices currently plugged in with their serial
+    /// numbers.
+    ///
+    /// With libusb every device is opened to read them, which fails for
+    /// devices without access rights, their serial number is left out.
+    pub fn list_serials(&self) -> Result<Vec<Descriptor>> {
+        Ok(self.devices(true)?.into_iter().map(|(_, descriptor)| descriptor).collect())
+    }
+
+    /// Watch for controllers being plugged in and out.
+    ///
+    /// Hotplug notifications are only available with the libusb backend, the
+    /// device list is polled otherwise.
+    pub fn monitor(&self) -> Result<Monitor> {
+        Monitor::new(self.usb.clone())
+    }
+
     /// Open a controller.
     pub fn open(&mut self) -> Result<Controller> {
-        for mut device in self.usb.devices()?.iter() {
-            let descriptor = device.device_descriptor()?;
+        self.open_by(Selector::Index(0))
+    }
 
-            if descriptor.vendor_id() != VENDOR_ID {
-                continue;
+    fn transports(&self, selector: Selector) -> Result<(Descriptor, Vec<Box<dyn Transport>>)> {
+        // Only look the serial numbers up when selecting by them.
+        let serials = matches!(selector, Selector::Serial(_));
+
+        let (device, descriptor) = self.devices(serials)?.into_iter().enumerate()
+            .find(|(index, (_, descriptor))| selector.matches(*index, descriptor))
+            .map(|(_, found)| found)
+            .ok_or(Error::NotFound)?;
+
+        let transports = device.open(descriptor.product)?;
+
+        Ok((descriptor, transports))
+    }
+
+    /// Open the controller matching the selector.
+    ///
+    /// On a dongle the first slot with a connected controller is picked,
+    /// falling back to the first slot.
+    pub fn open_by(&mut self, selector: Selector) -> Result<Controller> {
+        let mut slots = self.slots(selector)?;
+
+        match slots.iter().position(|&(_, connected)| connected) {
+            Some(index) => {
+                Ok(slots.swap_remove(index).0)
+            }
+
+            None => {
+                let (mut controller, _) = slots.swap_remove(0);
+                controller.reset()?;
+
+                Ok(controller)
+            }
+        }
+    }
+
+    /// Open every controller slot of the device matching the selector.
+    ///
+    /// With libusb all the controllers share the same handle, slots without a
+    /// connected controller are set up once it powers on.
+    pub fn open_slots(&mut self, selector: Selector) -> Result<Vec<Controller>> {
+        Ok(self.slots(selector)?.into_iter().map(|(controller, _)| controller).collect())
+    }
+
+    /// Open every slot, asking each one once whether a controller is
+    /// connected and setting up the connected ones.
+    fn slots(&mut self, selector: Selector) -> Result<Vec<(Controller, bool)>> {
+        let (descriptor, transports) = self.transports(selector)?;
+        let mut slots = Vec::with_capacity(transports.len());
+
+        for (slot, transport) in transports.into_iter().enumerate() {
+            let mut controller = Controller::attach(transport, descriptor.clone(), slot as u8);
+            let connected = controller.is_connected();
+
+            if connected {
+                controller.reset()?;
             }
 
-            for (&product, (&endpoint, &index)) in PRODUCT_ID.iter().zip(ENDPOINT.iter().zip(INDEX.iter())) {
-                if descriptor.product_id() != product {
+            slots.push((controller, connected));
+        }
+
+        Ok(slots)
+    }
+
+    /// Open the controller with the given serial number, as found in
+    /// `Details::serial.controller`, looking through every slot of every
+    /// device.
+    ///
+    /// Devices that can't be opened, for example because they are already in
+    /// use, are skipped.
+    pub fn open_serial(&mut self, value: &[u8; 10]) -> Result<Controller> {
+        for (device, descriptor) in self.devices(false)? {
+            let Ok(transports) = device.open(descriptor.product) else {
+                continue;
+            };
+
+            for (slot, transport) in transports.into_iter().enumerate() {
+                let mut controller = Controller::attach(transport, descriptor.clone(), slot as u8);
+
+                if !controller.is_connected() {
                     continue;
                 }
 
-                let handle = device.open()?;
+                if controller.details().is_ok_and(|details| &details.serial.controller == value) {
+                    controller.reset()?;
 
-                return Ok(Controller::new(device, handle, product, endpoint, index)?);
+                    return Ok(controller);
+                }
             }
         }
 
-        bail!(rusb::Error::NoDevice);
+        Err(Error::NotFound)
+    }
+
+    /// List the slots of the device matching the selector that currently
+    /// have a connected controller.
+    ///
+    /// Answering requires talking to the device, so it's opened for the
+    /// duration of the call. With libusb the interfaces are claimed, which
+    /// fails if the device is already in use, and the kernel drivers are
+    /// detached and stay detached. Prefer `Controller::is_connected` on
+    /// devices already opened, or the hidraw backend which leaves the kernel
+    /// drivers alone.
+    pub fn connected(&mut self, selector: Selector) -> Result<Vec<u8>> {
+        let (descriptor, transports) = self.transports(selector)?;
+
+        Ok(transports.into_iter().enumerate()
+            .map(|(slot, transport)| Controller::attach(transport, descriptor.clone(), slot as u8))
+            .filter_map(|mut controller| controller.is_connected().then(|| controller.slot()))
+            .collect())
     }
 }
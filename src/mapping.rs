@@ -0,0 +1,571 @@
+//! Input mapping with profiles.
+//!
+//! A profile is a list of layers, each binding physical inputs to actions.
+//! The first layer is always active, others are stacked on top of it by
+//! layer actions and take precedence for the inputs they bind. Profiles can
+//! be written in TOML or JSON:
+//!
+//! ```toml
+//! [[layers]]
+//! name = "base"
+//! bindings = [
+//!     { input = { button = "A" }, action = { key = 28 } },
+//!     { input = { pad = { side = "right", direction = "up", click = true } }, action = { mouse_button = "left" } },
+//!     { input = { trigger = { side = "left", threshold = 0.5 } }, action = { layer = "alt" } },
+//! ]
+//!
+//! [[layers]]
+//! name = "alt"
+//! bindings = [
+//!     { input = { button = "A" }, action = { macro = [{ press = 29 }, { press = 46 }, { release = 46 }, { release = 29 }] } },
+//! ]
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use serde::{Deserialize, Serialize};
+use crate::{Axis, Button, State, Error, Result};
+pub use crate::Side;
+use crate::uinput::{Event, Sink};
+use crate::uinput::code::*;
+#[cfg(target_os = "linux")]
+use crate::uinput::Device;
+
+/// Default distance from the center, as a fraction of the axis range, for
+/// pad directions and stick directions.
+const THRESHOLD: f32 = 0.3;
+
+/// A direction on a pad or the stick.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+
+    /// Closer to the center than the threshold.
+    Center,
+}
+
+fn threshold() -> f32 {
+    THRESHOLD
+}
+
+/// A physical input.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Input {
+    /// A button, by its name in `Button`.
+    Button(#[serde(with = "name")] Button),
+
+    /// A region of a touched pad.
+    Pad {
+        /// Which pad.
+        side: Side,
+
+        /// The region, split in four quarters around the center.
+        direction: Direction,
+
+        /// Only when the pad is clicked.
+        #[serde(default)]
+        click: bool,
+
+        /// The radius of the center region.
+        #[serde(default = "threshold")]
+        threshold: f32,
+    },
+
+    /// A trigger pressed past the threshold.
+    Trigger {
+        /// Which trigger.
+        side: Side,
+
+        /// The pressure from `0.0` to `1.0`.
+        threshold: f32,
+    },
+
+    /// The stick pushed in a direction past the threshold.
+    Stick {
+        /// The direction, `Direction::Center` never matches.
+        direction: Direction,
+
+        /// The distance from the center, from `0.0` to `1.0`.
+        #[serde(default = "threshold")]
+        threshold: f32,
+    },
+}
+
+/// Button names in profiles.
+mod name {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use crate::Button;
+
+    pub fn serialize<S: Serializer>(value: &Button, serializer: S) -> Result<S::Ok, S::Error> {
+        let name = value.iter_names().next().map_or("", |(name, _)| name);
+        serializer.serialize_str(name)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Button, D::Error> {
+        let name = String::deserialize(deserializer)?;
+
+        Button::from_name(&name)
+            .ok_or_else(|| de::Error::custom(format!("unknown button {}", name)))
+    }
+}
+
+/// A mouse button.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+}
+
+/// A gamepad button, named after its position.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    Mode,
+    LeftThumb,
+    RightThumb,
+}
+
+/// A step of a macro.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Step {
+    /// Press the key with the given code.
+    Press(u16),
+
+    /// Release the key with the given code.
+    Release(u16),
+}
+
+/// What an input does.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// Hold the key with the given evdev code, see `linux/input-event-codes.h`.
+    Key(u16),
+
+    /// Hold a mouse button.
+    MouseButton(MouseButton),
+
+    /// Hold a gamepad button.
+    GamepadButton(GamepadButton),
+
+    /// Run the steps when the input is activated.
+    Macro(Vec<Step>),
+
+    /// Activate the layer while the input is held.
+    Layer(String),
+
+    /// Activate or deactivate the layer when the input is activated.
+    ToggleLayer(String),
+}
+
+impl MouseButton {
+    fn code(self) -> u16 {
+        match self {
+            MouseButton::Left => BTN_LEFT,
+            MouseButton::Right => BTN_RIGHT,
+            MouseButton::Middle => BTN_MIDDLE,
+        }
+    }
+}
+
+impl GamepadButton {
+    fn code(self) -> u16 {
+        match self {
+            GamepadButton::South => BTN_SOUTH,
+            GamepadButton::East => BTN_EAST,
+            GamepadButton::North => BTN_NORTH,
+            GamepadButton::West => BTN_WEST,
+            GamepadButton::LeftBumper => BTN_TL,
+            GamepadButton::RightBumper => BTN_TR,
+            GamepadButton::LeftTrigger => BTN_TL2,
+            GamepadButton::RightTrigger => BTN_TR2,
+            GamepadButton::Select => BTN_SELECT,
+            GamepadButton::Start => BTN_START,
+            GamepadButton::Mode => BTN_MODE,
+            GamepadButton::LeftThumb => BTN_THUMBL,
+            GamepadButton::RightThumb => BTN_THUMBR,
+        }
+    }
+}
+
+impl Action {
+    /// The key held by the action, if any.
+    fn key(&self) -> Option<u16> {
+        match *self {
+            Action::Key(code) => Some(code),
+            Action::MouseButton(button) => Some(button.code()),
+            Action::GamepadButton(button) => Some(button.code()),
+            _ => None,
+        }
+    }
+}
+
+/// An input bound to an action.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct Binding {
+    /// The physical input.
+    pub input: Input,
+
+    /// What it does.
+    pub action: Action,
+}
+
+/// A set of bindings.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct Layer {
+    /// The name layer actions refer to.
+    pub name: String,
+
+    /// The bindings.
+    #[serde(default)]
+    pub bindings: Vec<Binding>,
+}
+
+/// A mapping profile.
+#[derive(Clone, PartialEq, Debug, Default, Deserialize, Serialize)]
+pub struct Profile {
+    /// The layers, the first one is always active.
+    pub layers: Vec<Layer>,
+}
+
+impl Profile {
+    /// Parse a TOML profile.
+    pub fn from_toml(value: &str) -> Result<Profile> {
+        let profile: Profile = toml::from_str(value).map_err(|e| Error::Profile(e.to_string()))?;
+        profile.validate()?;
+
+        Ok(profile)
+    }
+
+    /// Parse a JSON profile.
+    pub fn from_json(value: &str) -> Result<Profile> {
+        let profile: Profile = serde_json::from_str(value).map_err(|e| Error::Profile(e.to_string()))?;
+        profile.validate()?;
+
+        Ok(profile)
+    }
+
+    /// Load a profile, as JSON if the extension is `json` and as TOML
+    /// otherwise.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Profile> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)?;
+
+        if path.extension().is_some_and(|extension| extension == "json") {
+            Profile::from_json(&content)
+        } else {
+            Profile::from_toml(&content)
+        }
+    }
+
+    /// Check the layer actions refer to existing layers.
+    pub fn validate(&self) -> Result<()> {
+        for binding in self.layers.iter().flat_map(|layer| &layer.bindings) {
+            if let Action::Layer(ref name) | Action::ToggleLayer(ref name) = binding.action
+                && !self.layers.iter().any(|layer| &layer.name == name)
+            {
+                return Err(Error::Profile(format!("unknown layer {}", name)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A profile file reloaded when it changes.
+pub struct Watched {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl Watched {
+    /// Watch the profile at the given path, returning it as loaded now.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Result<(Watched, Profile)> {
+        let mut watched = Watched {
+            path: path.into(),
+            modified: None,
+        };
+
+        let profile = watched.check()?.ok_or(Error::NotFound)?;
+
+        Ok((watched, profile))
+    }
+
+    /// Load the profile again if the file has been modified since the last
+    /// check.
+    ///
+    /// A profile failing to load is reported once, until it's modified
+    /// again.
+    pub fn check(&mut self) -> Result<Option<Profile>> {
+        let modified = fs::metadata(&self.path)?.modified()?;
+
+        if self.modified == Some(modified) {
+            return Ok(None);
+        }
+
+        self.modified = Some(modified);
+
+        Profile::load(&self.path).map(Some)
+    }
+}
+
+fn direction(axis: Axis, direction: Direction, threshold: f32) -> bool {
+    let x = axis.x as f32 / i16::MAX as f32;
+    let y = axis.y as f32 / i16::MAX as f32;
+
+    if x.hypot(y) < threshold {
+        return direction == Direction::Center;
+    }
+
+    match direction {
+        Direction::Up => y >= x.abs(),
+        Direction::Down => -y >= x.abs(),
+        Direction::Left => -x > y.abs(),
+        Direction::Right => x > y.abs(),
+        Direction::Center => false,
+    }
+}
+
+impl Input {
+    /// Check if the input is active in the state.
+    pub fn is_active(&self, state: &State) -> bool {
+        let State::Input { buttons, trigger, pad, stick, .. } = *state else {
+            return false;
+        };
+
+        match *self {
+            Input::Button(button) =>
+                buttons.contains(button),
+
+            Input::Pad { side, direction: dir, click, threshold } => {
+                buttons.contains(side.touch()) && (!click || buttons.contains(side.click())) && direction(pad.get(side), dir, threshold)
+            }
+
+            Input::Trigger { side: Side::Left, threshold } =>
+                trigger.left >= threshold,
+
+            Input::Trigger { side: Side::Right, threshold } =>
+                trigger.right >= threshold,
+
+            Input::Stick { direction: dir, threshold } =>
+                dir != Direction::Center && direction(stick, dir, threshold),
+        }
+    }
+}
+
+/// Mapping engine, turning states into events according to a profile.
+pub struct Mapper<S: Sink> {
+    sink: S,
+    profile: Profile,
+    layers: Vec<usize>,
+    held: Vec<(Input, Action)>,
+    events: Vec<Event>,
+}
+
+#[cfg(target_os = "linux")]
+impl Mapper<Device> {
+    /// Create a uinput device able to emit every key, mouse button and
+    /// gamepad button.
+    pub fn create(name: &str, profile: Profile) -> Result<Mapper<Device>> {
+        let keys = (1..0x100).chain(BTN_LEFT..=BTN_MIDDLE).chain(BTN_SOUTH..=BTN_THUMBR).collect::<Vec<_>>();
+        let device = Device::new(name, &keys, &[], &[])?;
+
+        Ok(Mapper::new(device, profile))
+    }
+}
+
+impl<S: Sink> Mapper<S> {
+    /// Create a mapper emitting to the given sink.
+    pub fn new(sink: S, profile: Profile) -> Mapper<S> {
+        Mapper {
+            sink,
+            profile,
+            layers: vec![0],
+            held: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    /// The sink events are emitted to.
+    pub fn sink(&mut self) -> &mut S {
+        &mut self.sink
+    }
+
+    /// The profile in use.
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    /// The names of the active layers, from the bottom up.
+    pub fn layers(&self) -> Vec<&str> {
+        self.layers.iter()
+            .filter_map(|&index| self.profile.layers.get(index))
+            .map(|layer| layer.name.as_str())
+            .collect()
+    }
+
+    /// Switch to another profile, releasing everything held and going back
+    /// to the first layer.
+    pub fn set_profile(&mut self, profile: Profile) -> Result<()> {
+        self.release()?;
+        self.profile = profile;
+
+        Ok(())
+    }
+
+    /// Release everything held and go back to the first layer.
+    pub fn release(&mut self) -> Result<()> {
+        self.events.clear();
+
+        for (_, action) in std::mem::take(&mut self.held) {
+            if let Some(code) = action.key() {
+                self.events.push(Event::key(code, false));
+            }
+        }
+
+        self.layers = vec![0];
+        self.flush()
+    }
+
+    /// Evaluate the profile on the state, other states than `State::Input`
+    /// are ignored.
+    pub fn update(&mut self, state: &State) -> Result<()> {
+        if !matches!(*state, State::Input { .. }) {
+            return Ok(());
+        }
+
+        self.events.clear();
+
+        let mut index = 0;
+
+        while index < self.held.len() {
+            if self.held[index].0.is_active(state) {
+                index += 1;
+                continue;
+            }
+
+            let (_, action) = self.held.remove(index);
+            self.deactivate(&action);
+        }
+
+        for input in self.inputs() {
+            if !input.is_active(state) || self.held.iter().any(|(held, _)| *held == input) {
+                continue;
+            }
+
+            if let Some(action) = self.resolve(&input) {
+                self.activate(&action);
+                self.held.push((input, action));
+            }
+        }
+
+        self.flush()
+    }
+
+    /// Every input bound in the profile, once.
+    fn inputs(&self) -> Vec<Input> {
+        let mut inputs = Vec::<Input>::new();
+
+        for binding in self.profile.layers.iter().flat_map(|layer| &layer.bindings) {
+            if !inputs.contains(&binding.input) {
+                inputs.push(binding.input.clone());
+            }
+        }
+
+        inputs
+    }
+
+    /// The action bound to the input in the topmost active layer binding it.
+    fn resolve(&self, input: &Input) -> Option<Action> {
+        self.layers.iter().rev()
+            .filter_map(|&index| self.profile.layers.get(index))
+            .find_map(|layer| layer.bindings.iter().find(|binding| binding.input == *input))
+            .map(|binding| binding.action.clone())
+    }
+
+    fn layer(&self, name: &str) -> Option<usize> {
+        self.profile.layers.iter().position(|layer| layer.name == name)
+    }
+
+    fn activate(&mut self, action: &Action) {
+        match *action {
+            Action::Macro(ref steps) => {
+                for &step in steps {
+                    self.events.push(match step {
+                        Step::Press(code) => Event::key(code, true),
+                        Step::Release(code) => Event::key(code, false),
+                    });
+                }
+            }
+
+            Action::Layer(ref name) => {
+                if let Some(index) = self.layer(name) {
+                    self.layers.push(index);
+                }
+            }
+
+            Action::ToggleLayer(ref name) => {
+                if let Some(index) = self.layer(name) {
+                    match self.layers.iter().rposition(|&active| active == index) {
+                        Some(position) if position > 0 => { self.layers.remove(position); }
+                        Some(_) => (),
+                        None => self.layers.push(index),
+                    }
+                }
+            }
+
+            _ => {
+                if let Some(code) = action.key() {
+                    self.events.push(Event::key(code, true));
+                }
+            }
+        }
+    }
+
+    fn deactivate(&mut self, action: &Action) {
+        match *action {
+            Action::Layer(ref name) => {
+                if let Some(index) = self.layer(name)
+                    && let Some(position) = self.layers.iter().rposition(|&active| active == index)
+                    && position > 0
+                {
+                    self.layers.remove(position);
+                }
+            }
+
+            _ => {
+                if let Some(code) = action.key() {
+                    self.events.push(Event::key(code, false));
+                }
+            }
+        }
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if self.events.is_empty() {
+            return Ok(());
+        }
+
+        self.events.push(Event::sync());
+        self.sink.emit(&self.events)
+    }
+}
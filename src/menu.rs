@@ -0,0 +1,198 @@
+//! Touch menus on a trackpad.
+
+use std::f32::consts::TAU;
+use crate::{Axis, Pulse, Side, State};
+
+/// How the pad is divided into cells.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Layout {
+    /// Sectors around the center, numbered clockwise with the first one
+    /// centered at the top.
+    Radial {
+        /// The amount of sectors.
+        sectors: u16,
+
+        /// The radius of the center region where no sector is hovered, as a
+        /// fraction of the pad radius.
+        center: f32,
+    },
+
+    /// A grid numbered row by row, starting from the top left.
+    Grid {
+        /// The amount of columns.
+        columns: u16,
+
+        /// The amount of rows.
+        rows: u16,
+    },
+}
+
+impl Layout {
+    /// The amount of cells.
+    pub fn len(&self) -> usize {
+        match *self {
+            Layout::Radial { sectors, .. } =>
+                sectors as usize,
+
+            Layout::Grid { columns, rows } =>
+                columns as usize * rows as usize,
+        }
+    }
+
+    /// Check if the layout has no cells.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The cell at the given position, if any.
+    pub fn cell(&self, position: Axis) -> Option<usize> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let x = position.x as f32 / i16::MAX as f32;
+        let y = position.y as f32 / i16::MAX as f32;
+
+        match *self {
+            Layout::Radial { sectors, center } => {
+                if x.hypot(y) < center {
+                    return None;
+                }
+
+                // Clockwise from the top, shifted by half a sector so the
+                // first one is centered on it.
+                let width = TAU / sectors as f32;
+                let angle = x.atan2(y).rem_euclid(TAU) + width / 2.0;
+
+                Some((angle / width) as usize % sectors as usize)
+            }
+
+            Layout::Grid { columns, rows } => {
+                let column = ((x + 1.0) / 2.0 * columns as f32) as usize;
+                let row = ((1.0 - y) / 2.0 * rows as f32) as usize;
+
+                Some(row.min(rows as usize - 1) * columns as usize + column.min(columns as usize - 1))
+            }
+        }
+    }
+}
+
+/// When the hovered cell is selected.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Commit {
+    /// When the pad is clicked.
+    Click,
+
+    /// When the finger is lifted.
+    Release,
+}
+
+/// The outcome of a state for a menu.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Update {
+    /// The hovered cell, if any.
+    pub hovered: Option<usize>,
+
+    /// The cell selected by this state, if any.
+    pub selected: Option<usize>,
+
+    /// The haptic pulse to send, if any.
+    pub pulse: Option<Pulse>,
+}
+
+/// Menu driven by touching a trackpad.
+///
+/// The pad is divided in cells, the one under the finger is hovered and a
+/// pulse is produced every time the finger moves to another one. The hovered
+/// cell is selected when the pad is clicked or when the finger is lifted.
+pub struct Menu {
+    side: Side,
+    layout: Layout,
+    commit: Commit,
+    pulse: Option<Pulse>,
+
+    hovered: Option<usize>,
+    touched: bool,
+    clicked: bool,
+}
+
+impl Menu {
+    /// Create a menu on the given pad, selecting on click.
+    pub fn new(side: Side, layout: Layout) -> Menu {
+        Menu {
+            side,
+            layout,
+            commit: Commit::Click,
+            pulse: None,
+
+            hovered: None,
+            touched: false,
+            clicked: false,
+        }
+    }
+
+    /// The layout of the cells.
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// Change the layout of the cells, the hovered cell is reset.
+    pub fn set_layout(&mut self, value: Layout) {
+        self.layout = value;
+        self.hovered = None;
+    }
+
+    /// Change when the hovered cell is selected.
+    pub fn commit(&mut self, value: Commit) {
+        self.commit = value;
+    }
+
+    /// Produce the pulse when moving to another cell, `None` disables it.
+    pub fn pulse(&mut self, value: Option<Pulse>) {
+        self.pulse = value;
+    }
+
+    /// The hovered cell, if any.
+    pub fn hovered(&self) -> Option<usize> {
+        self.hovered
+    }
+
+    /// Handle a state, other states than `State::Input` are ignored.
+    pub fn update(&mut self, state: &State) -> Update {
+        let State::Input { buttons, pad, .. } = *state else {
+            return Update { hovered: self.hovered, .. Update::default() };
+        };
+
+        let touched = buttons.contains(self.side.touch());
+        let clicked = buttons.contains(self.side.click());
+        let mut update = Update::default();
+
+        let hovered = if touched {
+            self.layout.cell(pad.get(self.side))
+        } else {
+            None
+        };
+
+        if hovered.is_some() && hovered != self.hovered {
+            update.pulse = self.pulse;
+        }
+
+        update.selected = match self.commit {
+            Commit::Click if clicked && !self.clicked =>
+                hovered,
+
+            Commit::Release if self.touched && !touched =>
+                self.hovered,
+
+            _ =>
+                None,
+        };
+
+        self.hovered = hovered;
+        self.touched = touched;
+        self.clicked = clicked;
+
+        update.hovered = hovered;
+        update
+    }
+}
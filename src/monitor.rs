@@ -0,0 +1,207 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use rusb::UsbContext;
+
+use crate::{Controller, Descriptor, State, Result};
+use crate::manager::Device;
+use crate::VENDOR_ID;
+
+const INTERVAL: Duration = Duration::from_secs(1);
+
+/// Hotplug and connection events.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Event {
+    /// A wired controller or a dongle has been plugged in.
+    Arrived(Descriptor),
+
+    /// A wired controller or a dongle has been unplugged.
+    Left(Descriptor),
+
+    /// A wireless controller connected to the given slot of a dongle.
+    Connected(Descriptor, u8),
+
+    /// A wireless controller disconnected from the given slot of a dongle.
+    Disconnected(Descriptor, u8),
+}
+
+struct Notify(Arc<AtomicBool>);
+
+impl rusb::Hotplug<rusb::Context> for Notify {
+    fn device_arrived(&mut self, _device: rusb::Device<rusb::Context>) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    fn device_left(&mut self, _device: rusb::Device<rusb::Context>) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Hotplug monitor.
+///
+/// Uses libusb hotplug notifications where available and falls back to
+/// polling the device list otherwise. Devices already plugged in when the
+/// monitor is created are reported as arrived on the first call to `next`.
+pub struct Monitor {
+    usb: Option<rusb::Context>,
+    registration: Option<rusb::Registration<rusb::Context>>,
+    dirty: Arc<AtomicBool>,
+    interval: Duration,
+    polled: Option<Instant>,
+
+    known: Vec<Descriptor>,
+    slots: HashMap<(u8, u8, u8), bool>,
+    pending: VecDeque<Event>,
+}
+
+fn same(a: &Descriptor, b: &Descriptor) -> bool {
+    a.bus == b.bus && a.address == b.address && a.product == b.product
+}
+
+impl Monitor {
+    pub(crate) fn new(usb: Option<rusb::Context>) -> Result<Monitor> {
+        let dirty = Arc::new(AtomicBool::new(true));
+
+        let registration = match usb {
+            Some(ref usb) if rusb::has_hotplug() => {
+                let mut builder = rusb::HotplugBuilder::new();
+                builder.vendor_id(VENDOR_ID);
+
+                Some(builder.register(usb, Box::new(Notify(dirty.clone())))?)
+            }
+
+            _ => None,
+        };
+
+        Ok(Monitor {
+            usb,
+            registration,
+            dirty,
+            interval: INTERVAL,
+            polled: None,
+
+            known: Vec::new(),
+            slots: HashMap::new(),
+            pending: VecDeque::new(),
+        })
+    }
+
+    /// Check if hotplug notifications are used instead of polling.
+    pub fn is_hotplug(&self) -> bool {
+        self.registration.is_some()
+    }
+
+    /// Change how often the device list is polled without hotplug support.
+    pub fn interval(&mut self, value: Duration) {
+        self.interval = value;
+    }
+
+    /// Wait up to `timeout` for the next event.
+    pub fn next(&mut self, timeout: Duration) -> Result<Option<Event>> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Some(event));
+            }
+
+            let now = Instant::now();
+
+            if !self.is_hotplug() && self.polled.is_none_or(|at| now >= at + self.interval) {
+                self.dirty.store(true, Ordering::SeqCst);
+            }
+
+            if self.dirty.swap(false, Ordering::SeqCst) {
+                self.rescan()?;
+                continue;
+            }
+
+            if now >= deadline {
+                return Ok(None);
+            }
+
+            let remaining = deadline - now;
+
+            if let (Some(usb), true) = (self.usb.as_ref(), self.is_hotplug()) {
+                usb.handle_events(Some(remaining))?;
+            } else {
+                let poll = self.polled.map_or(now, |at| at + self.interval);
+                thread::sleep(remaining.min(poll.saturating_duration_since(now)));
+            }
+        }
+    }
+
+    fn rescan(&mut self) -> Result<()> {
+        self.polled = Some(Instant::now());
+        let found = Device::scan(self.usb.as_ref(), false)?;
+
+        let (kept, gone): (Vec<_>, Vec<_>) = self.known.drain(..)
+            .partition(|known| found.iter().any(|(_, d)| same(known, d)));
+
+        self.known = kept;
+
+        for descriptor in gone {
+            let mut slots = self.slots.iter()
+                .filter(|&(&(bus, address, _), &connected)|
+                    connected && bus == descriptor.bus && address == descriptor.address)
+                .map(|(&(_, _, slot), _)| slot)
+                .collect::<Vec<_>>();
+
+            slots.sort();
+
+            for slot in slots {
+                self.pending.push_back(Event::Disconnected(descriptor.clone(), slot));
+            }
+
+            self.slots.retain(|&(bus, address, _), _| bus != descriptor.bus || address != descriptor.address);
+            self.pending.push_back(Event::Left(descriptor));
+        }
+
+        for (device, mut descriptor) in found {
+            if self.known.iter().any(|known| same(known, &descriptor)) {
+                continue;
+            }
+
+            device.identify(&mut descriptor);
+            self.known.push(descriptor.clone());
+            self.pending.push_back(Event::Arrived(descriptor));
+        }
+
+        Ok(())
+    }
+
+    fn update(&mut self, controller: &Controller, connected: bool) {
+        if !controller.is_remote() {
+            return;
+        }
+
+        let descriptor = controller.descriptor();
+        let slot = controller.slot();
+        let previous = self.slots.insert((descriptor.bus, descriptor.address, slot), connected);
+
+        if previous.unwrap_or(false) != connected {
+            self.pending.push_back(if connected {
+                Event::Connected(descriptor.clone(), slot)
+            } else {
+                Event::Disconnected(descriptor.clone(), slot)
+            });
+        }
+    }
+
+    /// Derive wireless connection events from a state read from the
+    /// controller.
+    pub fn observe(&mut self, controller: &Controller, state: &State) {
+        if let State::Power(on) = *state {
+            self.update(controller, on);
+        }
+    }
+
+    /// Check if the wireless controller is connected, queueing an event if
+    /// that changed.
+    pub fn check(&mut self, controller: &mut Controller) {
+        let connected = controller.is_connected();
+        self.update(controller, connected);
+    }
+}
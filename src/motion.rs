@@ -0,0 +1,430 @@
+//! Motion sensors decoding and fusion.
+//!
+//! The raw values of input states are in the frame of the controller, in
+//! report order: the `pitch`, `yaw` and `roll` fields are the X, Y and Z
+//! axes. The gyro measures up to 2000 degrees per second and the
+//! accelerometer up to 2 g in each direction.
+//!
+//! Orientations map the frame of the controller to the world frame, where Z
+//! points up.
+
+use std::f32::consts::PI;
+use std::ops::{Add, Mul, Neg, Sub};
+use std::time::Duration;
+use crate::{Angles, Quaternion, State};
+
+/// Degrees per second for each unit of the gyro.
+pub const GYRO: f32 = 2000.0 / 32768.0;
+
+/// Standard gravities for each unit of the accelerometer.
+pub const ACCELERATION: f32 = 2.0 / 32768.0;
+
+/// Default time between two input reports.
+const PERIOD: Duration = Duration::from_millis(4);
+
+/// Angular velocity in degrees per second under which the controller may be
+/// at rest.
+const REST: f32 = 5.0;
+
+/// Distance from a standard gravity under which the controller may be at
+/// rest.
+const STILL: f32 = 0.05;
+
+/// A 3D vector.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct Vector {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl Vector {
+    /// Create a vector.
+    pub const fn new(x: f32, y: f32, z: f32) -> Vector {
+        Vector { x, y, z }
+    }
+
+    /// Decode raw sensor values with the given units.
+    pub fn decode(angles: Angles, scale: f32) -> Vector {
+        Vector::new(angles.pitch as f32 * scale, angles.yaw as f32 * scale, angles.roll as f32 * scale)
+    }
+
+    /// The dot product.
+    pub fn dot(self, other: Vector) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    /// The cross product.
+    pub fn cross(self, other: Vector) -> Vector {
+        Vector::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    /// The length.
+    pub fn length(self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    /// The vector with a length of one, or the zero vector.
+    pub fn normalized(self) -> Vector {
+        let length = self.length();
+
+        if length == 0.0 {
+            return self;
+        }
+
+        self * (1.0 / length)
+    }
+}
+
+impl Add for Vector {
+    type Output = Vector;
+
+    fn add(self, other: Vector) -> Vector {
+        Vector::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl Sub for Vector {
+    type Output = Vector;
+
+    fn sub(self, other: Vector) -> Vector {
+        Vector::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl Mul<f32> for Vector {
+    type Output = Vector;
+
+    fn mul(self, scale: f32) -> Vector {
+        Vector::new(self.x * scale, self.y * scale, self.z * scale)
+    }
+}
+
+impl Neg for Vector {
+    type Output = Vector;
+
+    fn neg(self) -> Vector {
+        Vector::new(-self.x, -self.y, -self.z)
+    }
+}
+
+/// The world up direction.
+const UP: Vector = Vector::new(0.0, 0.0, 1.0);
+
+/// A rotation as a unit quaternion.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Rotation {
+    pub w: f32,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl Default for Rotation {
+    fn default() -> Rotation {
+        Rotation::IDENTITY
+    }
+}
+
+impl Rotation {
+    /// No rotation.
+    pub const IDENTITY: Rotation = Rotation { w: 1.0, x: 0.0, y: 0.0, z: 0.0 };
+
+    /// Decode the raw quaternion of the firmware, the identity if it's zero
+    /// with the sensors off.
+    pub fn decode(quaternion: Quaternion) -> Rotation {
+        Rotation {
+            w: quaternion.w as f32,
+            x: quaternion.x as f32,
+            y: quaternion.y as f32,
+            z: quaternion.z as f32,
+        }.normalized()
+    }
+
+    /// A rotation of the given degrees around the axis.
+    pub fn around(axis: Vector, degrees: f32) -> Rotation {
+        let axis = axis.normalized();
+        let (sin, cos) = (degrees.to_radians() / 2.0).sin_cos();
+
+        Rotation { w: cos, x: axis.x * sin, y: axis.y * sin, z: axis.z * sin }
+    }
+
+    /// The shortest rotation turning the direction `from` into `to`.
+    pub fn between(from: Vector, to: Vector) -> Rotation {
+        let from = from.normalized();
+        let to = to.normalized();
+
+        if from.dot(to) < -0.9999 {
+            // Opposite directions, any perpendicular axis does.
+            let other = if from.x.abs() < 0.9 { Vector::new(1.0, 0.0, 0.0) } else { Vector::new(0.0, 1.0, 0.0) };
+            return Rotation::around(from.cross(other), 180.0);
+        }
+
+        let axis = from.cross(to);
+
+        Rotation { w: 1.0 + from.dot(to), x: axis.x, y: axis.y, z: axis.z }.normalized()
+    }
+
+    /// The inverse rotation.
+    pub fn conjugate(self) -> Rotation {
+        Rotation { w: self.w, x: -self.x, y: -self.y, z: -self.z }
+    }
+
+    /// The rotation with a length of one, or the identity.
+    pub fn normalized(self) -> Rotation {
+        let length = (self.w * self.w + self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
+
+        if length == 0.0 || !length.is_finite() {
+            return Rotation::IDENTITY;
+        }
+
+        Rotation { w: self.w / length, x: self.x / length, y: self.y / length, z: self.z / length }
+    }
+
+    /// Rotate a vector.
+    pub fn rotate(self, vector: Vector) -> Vector {
+        let axis = Vector::new(self.x, self.y, self.z);
+        let t = axis.cross(vector) * 2.0;
+
+        vector + t * self.w + axis.cross(t)
+    }
+
+    /// The angle of the rotation in degrees.
+    pub fn angle(self) -> f32 {
+        2.0 * self.w.abs().min(1.0).acos().to_degrees()
+    }
+}
+
+impl Mul for Rotation {
+    type Output = Rotation;
+
+    /// Apply `other` first, then `self`.
+    fn mul(self, other: Rotation) -> Rotation {
+        Rotation {
+            w: self.w * other.w - self.x * other.x - self.y * other.y - self.z * other.z,
+            x: self.w * other.x + self.x * other.w + self.y * other.z - self.z * other.y,
+            y: self.w * other.y - self.x * other.z + self.y * other.w + self.z * other.x,
+            z: self.w * other.z + self.x * other.y - self.y * other.x + self.z * other.w,
+        }
+    }
+}
+
+/// How the orientation is computed.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Filter {
+    /// Use the quaternion computed by the firmware.
+    Firmware,
+
+    /// Integrate the gyro and pull the tilt towards the accelerometer by the
+    /// given fraction per second.
+    Complementary(f32),
+
+    /// The Madgwick filter with the given gain, around `0.1`.
+    Madgwick(f32),
+}
+
+/// The decoded and fused motion for an input state.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Motion {
+    /// The time since the first state, from the sequence numbers.
+    pub time: Duration,
+
+    /// The angular velocity in degrees per second, with the yaw drift
+    /// removed.
+    pub gyro: Vector,
+
+    /// The acceleration in standard gravities.
+    pub acceleration: Vector,
+
+    /// The orientation of the controller.
+    pub orientation: Rotation,
+
+    /// The direction of gravity in the frame of the controller.
+    pub gravity: Vector,
+}
+
+/// Sensor fusion turning input states into orientations.
+pub struct Fusion {
+    filter: Filter,
+    period: Duration,
+    drift: Option<f32>,
+
+    orientation: Option<Rotation>,
+    first: u32,
+    last: u32,
+    yaw: f32,
+}
+
+impl Default for Fusion {
+    fn default() -> Fusion {
+        Fusion::new(Filter::Firmware)
+    }
+}
+
+impl Fusion {
+    /// Create a fusion with the given filter.
+    pub fn new(filter: Filter) -> Fusion {
+        Fusion {
+            filter,
+            period: PERIOD,
+            drift: None,
+
+            orientation: None,
+            first: 0,
+            last: 0,
+            yaw: 0.0,
+        }
+    }
+
+    /// Change the time between two input reports, used to turn sequence
+    /// numbers into timestamps.
+    pub fn period(&mut self, value: Duration) {
+        self.period = value;
+    }
+
+    /// Learn the rotation around the vertical measured while the controller
+    /// is at rest by the given fraction per second and remove it, `None`
+    /// disables drift compensation.
+    pub fn drift(&mut self, rate: Option<f32>) {
+        self.drift = rate;
+    }
+
+    /// The yaw drift learned so far, in degrees per second.
+    pub fn yaw_drift(&self) -> f32 {
+        self.yaw
+    }
+
+    /// Forget the orientation, starting over with the next state.
+    pub fn reset(&mut self) {
+        self.orientation = None;
+        self.yaw = 0.0;
+    }
+
+    /// Handle a state, other states than `State::Input` are ignored.
+    pub fn update(&mut self, state: &State) -> Option<Motion> {
+        let State::Input { sequence, gyro, acceleration, quaternion, .. } = *state else {
+            return None;
+        };
+
+        let gyro = Vector::decode(gyro, GYRO);
+        let acceleration = Vector::decode(acceleration, ACCELERATION);
+        let measured = acceleration.normalized();
+
+        let Some(orientation) = self.orientation else {
+            self.first = sequence;
+            self.last = sequence;
+
+            let orientation = match self.filter {
+                Filter::Firmware => Rotation::decode(quaternion),
+                _ if measured == Vector::default() => Rotation::IDENTITY,
+                _ => Rotation::between(measured, UP),
+            };
+
+            return Some(self.motion(orientation, gyro, acceleration));
+        };
+
+        // Missed reports still count.
+        let elapsed = self.period * sequence.wrapping_sub(self.last);
+        self.last = sequence;
+
+        let seconds = elapsed.as_secs_f32();
+        let up = orientation.conjugate().rotate(UP);
+
+        if let Some(rate) = self.drift {
+            let rest = gyro.length() < REST && (acceleration.length() - 1.0).abs() < STILL;
+
+            if rest {
+                let yaw = gyro.dot(measured);
+                self.yaw += (yaw - self.yaw) * (rate * seconds).min(1.0);
+            }
+        }
+
+        let gyro = gyro - up * self.yaw;
+
+        let orientation = match self.filter {
+            Filter::Firmware =>
+                Rotation::decode(quaternion),
+
+            Filter::Complementary(factor) => {
+                let orientation = orientation * Rotation::around(gyro, gyro.length() * seconds);
+                let up = orientation.conjugate().rotate(UP);
+
+                if measured == Vector::default() {
+                    orientation
+                } else {
+                    // Turn by a fraction of the tilt error, as seen from the
+                    // controller.
+                    let error = Rotation::between(measured, up);
+                    let fraction = (factor * seconds).min(1.0);
+
+                    orientation * Rotation::around(Vector::new(error.x, error.y, error.z), error.angle() * fraction)
+                }
+            }
+
+            Filter::Madgwick(beta) =>
+                madgwick(orientation, gyro, measured, beta, seconds),
+        };
+
+        Some(self.motion(orientation.normalized(), gyro, acceleration))
+    }
+
+    fn motion(&mut self, orientation: Rotation, gyro: Vector, acceleration: Vector) -> Motion {
+        self.orientation = Some(orientation);
+
+        Motion {
+            time: self.period * self.last.wrapping_sub(self.first),
+            gyro,
+            acceleration,
+            orientation,
+            gravity: -orientation.conjugate().rotate(UP),
+        }
+    }
+}
+
+/// A step of the Madgwick filter, the accelerometer direction being zero
+/// when unknown.
+fn madgwick(q: Rotation, gyro: Vector, measured: Vector, beta: f32, seconds: f32) -> Rotation {
+    let Rotation { w, x, y, z } = q;
+    let rate = gyro * (PI / 180.0);
+
+    // The change from the gyro.
+    let mut dw = 0.5 * (-x * rate.x - y * rate.y - z * rate.z);
+    let mut dx = 0.5 * (w * rate.x + y * rate.z - z * rate.y);
+    let mut dy = 0.5 * (w * rate.y - x * rate.z + z * rate.x);
+    let mut dz = 0.5 * (w * rate.z + x * rate.y - y * rate.x);
+
+    if measured != Vector::default() {
+        // The gradient of the error between the expected and measured up
+        // directions.
+        let f1 = 2.0 * (x * z - w * y) - measured.x;
+        let f2 = 2.0 * (w * x + y * z) - measured.y;
+        let f3 = 2.0 * (0.5 - x * x - y * y) - measured.z;
+
+        let step = Rotation {
+            w: -2.0 * y * f1 + 2.0 * x * f2,
+            x: 2.0 * z * f1 + 2.0 * w * f2 - 4.0 * x * f3,
+            y: -2.0 * w * f1 + 2.0 * z * f2 - 4.0 * y * f3,
+            z: 2.0 * x * f1 + 2.0 * y * f2,
+        };
+
+        if step != (Rotation { w: 0.0, x: 0.0, y: 0.0, z: 0.0 }) {
+            let step = step.normalized();
+
+            dw -= beta * step.w;
+            dx -= beta * step.x;
+            dy -= beta * step.y;
+            dz -= beta * step.z;
+        }
+    }
+
+    Rotation {
+        w: w + dw * seconds,
+        x: x + dx * seconds,
+        y: y + dy * seconds,
+        z: z + dz * seconds,
+    }
+}
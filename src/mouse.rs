@@ -0,0 +1,295 @@
+//! Right trackpad mouse emulation.
+
+use std::time::{Duration, Instant};
+use crate::{Axis, Button, Pulse, State, Result};
+use crate::uinput::{Event, Sink};
+use crate::uinput::code::*;
+#[cfg(target_os = "linux")]
+use crate::uinput::Device;
+
+/// Default pixels per pad unit.
+const SENSITIVITY: f32 = 0.02;
+
+/// Default inertia friction, per second.
+const FRICTION: f32 = 4.0;
+
+/// Speed in pixels per second under which inertia stops.
+const STOPPED: f32 = 20.0;
+
+/// How the pointer speeds up as the finger moves faster.
+///
+/// The speed is measured per millisecond before acceleration, in pixels for
+/// the mouse, the motion is multiplied by the resulting gain.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Acceleration {
+    /// The motion follows the finger.
+    Flat,
+
+    /// The gain is `1 + factor * speed`, up to `limit`.
+    Linear {
+        /// How fast the gain grows.
+        factor: f32,
+
+        /// The maximum gain.
+        limit: f32,
+    },
+
+    /// The gain is `1 + (factor * speed) ^ exponent`, up to `limit`.
+    Power {
+        /// How fast the gain grows.
+        factor: f32,
+
+        /// The curve exponent.
+        exponent: f32,
+
+        /// The maximum gain.
+        limit: f32,
+    },
+}
+
+impl Acceleration {
+    /// The gain for the given speed.
+    pub fn gain(&self, speed: f32) -> f32 {
+        match *self {
+            Acceleration::Flat =>
+                1.0,
+
+            Acceleration::Linear { factor, limit } =>
+                (1.0 + factor * speed).min(limit),
+
+            Acceleration::Power { factor, exponent, limit } =>
+                (1.0 + (factor * speed).powf(exponent)).min(limit),
+        }
+    }
+}
+
+/// Mouse driven by the right trackpad.
+///
+/// Touch motion moves the pointer, clicking the pad is the left button, and
+/// lifting the finger while moving lets the pointer glide like a trackball
+/// until friction stops it. Optionally a haptic tick is produced every given
+/// distance travelled.
+pub struct Mouse<S: Sink> {
+    sink: S,
+    sensitivity: f32,
+    acceleration: Acceleration,
+    friction: Option<f32>,
+    tick: Option<(f32, Pulse)>,
+
+    last: Option<Axis>,
+    clicked: bool,
+    velocity: (f32, f32),
+    remainder: (f32, f32),
+    travelled: f32,
+    updated: Option<Instant>,
+    events: Vec<Event>,
+}
+
+#[cfg(target_os = "linux")]
+impl Mouse<Device> {
+    /// Create a uinput mouse device.
+    ///
+    /// The device also has the middle and right buttons and the wheels, so
+    /// it can be shared with other emulation layers.
+    pub fn create(name: &str) -> Result<Mouse<Device>> {
+        let device = Device::new(name,
+            &[BTN_LEFT, BTN_RIGHT, BTN_MIDDLE],
+            &[REL_X, REL_Y, REL_WHEEL, REL_HWHEEL, REL_WHEEL_HI_RES, REL_HWHEEL_HI_RES],
+            &[])?;
+
+        Ok(Mouse::new(device))
+    }
+}
+
+impl<S: Sink> Mouse<S> {
+    /// Create a mouse emitting to the given sink.
+    pub fn new(sink: S) -> Mouse<S> {
+        Mouse {
+            sink,
+            sensitivity: SENSITIVITY,
+            acceleration: Acceleration::Flat,
+            friction: Some(FRICTION),
+            tick: None,
+
+            last: None,
+            clicked: false,
+            velocity: (0.0, 0.0),
+            remainder: (0.0, 0.0),
+            travelled: 0.0,
+            updated: None,
+            events: Vec::new(),
+        }
+    }
+
+    /// The sink events are emitted to.
+    pub fn sink(&mut self) -> &mut S {
+        &mut self.sink
+    }
+
+    /// Change how many pixels a pad unit moves the pointer.
+    pub fn sensitivity(&mut self, value: f32) {
+        self.sensitivity = value;
+    }
+
+    /// Change the acceleration curve.
+    pub fn acceleration(&mut self, value: Acceleration) {
+        self.acceleration = value;
+    }
+
+    /// Change how fast the pointer slows down after lifting the finger, as a
+    /// fraction of the speed lost per second, `None` disables inertia.
+    pub fn inertia(&mut self, friction: Option<f32>) {
+        self.friction = friction;
+    }
+
+    /// Produce the pulse every `distance` pixels travelled, `None` disables
+    /// the ticks.
+    pub fn ticks(&mut self, value: Option<(f32, Pulse)>) {
+        self.tick = value;
+    }
+
+    /// Check if the pointer is still gliding after the finger was lifted.
+    pub fn is_gliding(&self) -> bool {
+        self.last.is_none() && self.velocity != (0.0, 0.0)
+    }
+
+    /// Handle a state received at the given time, other states than
+    /// `State::Input` are ignored.
+    ///
+    /// Returns the haptic ticks to send, if any.
+    pub fn update(&mut self, state: &State, at: Instant) -> Result<Option<Pulse>> {
+        let State::Input { buttons, pad, .. } = *state else {
+            return Ok(None);
+        };
+
+        self.events.clear();
+
+        let elapsed = self.elapsed(at);
+        let clicked = buttons.contains(Button::TRACK);
+
+        if clicked != self.clicked {
+            self.clicked = clicked;
+            self.events.push(Event::key(BTN_LEFT, clicked));
+        }
+
+        let motion = if buttons.contains(Button::TRACK_TOUCH) {
+            self.touch(pad.right, elapsed)
+        } else {
+            self.last = None;
+            self.glide(elapsed)
+        };
+
+        self.flush(motion)
+    }
+
+    /// Move the pointer while it glides without any new state, to be called
+    /// regularly as reports may stop coming in once the pad is released.
+    pub fn coast(&mut self, at: Instant) -> Result<Option<Pulse>> {
+        self.events.clear();
+
+        let elapsed = self.elapsed(at);
+
+        let motion = if self.last.is_none() {
+            self.glide(elapsed)
+        } else {
+            (0.0, 0.0)
+        };
+
+        self.flush(motion)
+    }
+
+    fn elapsed(&mut self, at: Instant) -> Duration {
+        let elapsed = self.updated.map_or(Duration::ZERO, |updated| at.saturating_duration_since(updated));
+        self.updated = Some(at);
+
+        elapsed
+    }
+
+    fn touch(&mut self, position: Axis, elapsed: Duration) -> (f32, f32) {
+        let Some(last) = self.last.replace(position) else {
+            // Touching the pad stops the pointer.
+            self.velocity = (0.0, 0.0);
+            return (0.0, 0.0);
+        };
+
+        let dx = (position.x as f32 - last.x as f32) * self.sensitivity;
+        let dy = (last.y as f32 - position.y as f32) * self.sensitivity;
+
+        if elapsed.is_zero() {
+            return (dx, dy);
+        }
+
+        let millis = elapsed.as_secs_f32() * 1000.0;
+        let gain = self.acceleration.gain(dx.hypot(dy) / millis);
+        let motion = (dx * gain, dy * gain);
+
+        // Smooth the speed out a little, a single report is noisy.
+        let seconds = elapsed.as_secs_f32();
+        self.velocity = (
+            (self.velocity.0 + motion.0 / seconds) / 2.0,
+            (self.velocity.1 + motion.1 / seconds) / 2.0,
+        );
+
+        motion
+    }
+
+    fn glide(&mut self, elapsed: Duration) -> (f32, f32) {
+        let Some(friction) = self.friction else {
+            self.velocity = (0.0, 0.0);
+            return (0.0, 0.0);
+        };
+
+        let seconds = elapsed.as_secs_f32();
+        let motion = (self.velocity.0 * seconds, self.velocity.1 * seconds);
+
+        let decay = (-friction * seconds).exp();
+        self.velocity = (self.velocity.0 * decay, self.velocity.1 * decay);
+
+        if self.velocity.0.hypot(self.velocity.1) < STOPPED {
+            self.velocity = (0.0, 0.0);
+        }
+
+        motion
+    }
+
+    fn flush(&mut self, motion: (f32, f32)) -> Result<Option<Pulse>> {
+        self.remainder.0 += motion.0;
+        self.remainder.1 += motion.1;
+
+        let x = self.remainder.0.trunc();
+        let y = self.remainder.1.trunc();
+        self.remainder.0 -= x;
+        self.remainder.1 -= y;
+
+        if x != 0.0 {
+            self.events.push(Event::relative(REL_X, x as i32));
+        }
+
+        if y != 0.0 {
+            self.events.push(Event::relative(REL_Y, y as i32));
+        }
+
+        if !self.events.is_empty() {
+            self.events.push(Event::sync());
+            self.sink.emit(&self.events)?;
+        }
+
+        let Some((distance, pulse)) = self.tick else {
+            return Ok(None);
+        };
+
+        self.travelled += motion.0.hypot(motion.1);
+
+        if distance <= 0.0 || self.travelled < distance {
+            return Ok(None);
+        }
+
+        let count = (self.travelled / distance).floor();
+        self.travelled -= count * distance;
+
+        Ok(Some(Pulse {
+            count: (count * pulse.count as f32).min(u16::MAX as f32) as u16,
+            .. pulse
+        }))
+    }
+}
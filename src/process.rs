@@ -0,0 +1,404 @@
+//! Stick, pad and trigger processing.
+//!
+//! Positions are normalized from `-1.0` to `1.0` and go through the inner
+//! deadzone, the outer deadzone, the response curve and the anti-deadzone, in
+//! that order.
+
+use crate::{Axis, Pulse, Side, State};
+
+/// The region around the center where input is ignored, as a fraction of the
+/// range. The remaining range is stretched so the output still starts at zero.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Deadzone {
+    /// No deadzone.
+    None,
+
+    /// A circle, keeping the direction intact.
+    Radial(f32),
+
+    /// A square, each axis being handled on its own.
+    Axial(f32),
+
+    /// A circle where positions close to an axis also snap to it, making
+    /// straight motion easier.
+    Cross(f32),
+}
+
+/// How the distance from the center is mapped to the output.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Curve {
+    /// The output follows the input.
+    Linear,
+
+    /// The input raised to the given exponent, above `1.0` gives more
+    /// precision near the center.
+    Power(f32),
+
+    /// The output at evenly spaced inputs from `0.0` to `1.0`, interpolated
+    /// in between.
+    Table(Vec<f32>),
+}
+
+impl Curve {
+    /// The output for an input from `0.0` to `1.0`.
+    pub fn apply(&self, value: f32) -> f32 {
+        let value = value.clamp(0.0, 1.0);
+
+        match self {
+            Curve::Linear =>
+                value,
+
+            Curve::Power(exponent) =>
+                value.powf(*exponent),
+
+            Curve::Table(table) => match table.len() {
+                0 => value,
+                1 => table[0],
+                len => {
+                    let position = value * (len - 1) as f32;
+                    let index = (position as usize).min(len - 2);
+                    let fraction = position - index as f32;
+
+                    table[index] + (table[index + 1] - table[index]) * fraction
+                }
+            },
+        }
+    }
+}
+
+/// Processing of an axis.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Processor {
+    deadzone: Deadzone,
+    outer: f32,
+    curve: Curve,
+    anti: f32,
+}
+
+impl Default for Processor {
+    fn default() -> Processor {
+        Processor::new()
+    }
+}
+
+impl Processor {
+    /// Create a processor leaving the axis untouched.
+    pub fn new() -> Processor {
+        Processor {
+            deadzone: Deadzone::None,
+            outer: 1.0,
+            curve: Curve::Linear,
+            anti: 0.0,
+        }
+    }
+
+    /// Change the inner deadzone.
+    pub fn deadzone(&mut self, value: Deadzone) {
+        self.deadzone = value;
+    }
+
+    /// Change the distance from the center, as a fraction of the range, past
+    /// which the output is saturated.
+    pub fn outer(&mut self, value: f32) {
+        self.outer = value;
+    }
+
+    /// Change the response curve.
+    pub fn curve(&mut self, value: Curve) {
+        self.curve = value;
+    }
+
+    /// Change the smallest output outside of the deadzone, to compensate for
+    /// the deadzone of a game.
+    pub fn anti_deadzone(&mut self, value: f32) {
+        self.anti = value;
+    }
+
+    /// Process a position, returning the normalized output.
+    pub fn process(&self, axis: Axis) -> (f32, f32) {
+        let x = (axis.x as f32 / i16::MAX as f32).clamp(-1.0, 1.0);
+        let y = (axis.y as f32 / i16::MAX as f32).clamp(-1.0, 1.0);
+
+        match self.deadzone {
+            Deadzone::None =>
+                self.radial(x, y, 0.0),
+
+            Deadzone::Radial(size) =>
+                self.radial(x, y, size),
+
+            Deadzone::Axial(size) =>
+                (self.shape(x.abs(), size).copysign(x), self.shape(y.abs(), size).copysign(y)),
+
+            Deadzone::Cross(size) => {
+                let x = if x.abs() < size { 0.0 } else { x };
+                let y = if y.abs() < size { 0.0 } else { y };
+
+                self.radial(x, y, size)
+            }
+        }
+    }
+
+    /// Process a position, returning the output on the full axis range.
+    pub fn axis(&self, axis: Axis) -> Axis {
+        let (x, y) = self.process(axis);
+
+        Axis {
+            x: (x * i16::MAX as f32).round() as i16,
+            y: (y * i16::MAX as f32).round() as i16,
+        }
+    }
+
+    fn radial(&self, x: f32, y: f32, size: f32) -> (f32, f32) {
+        let distance = x.hypot(y);
+
+        if distance == 0.0 {
+            return (0.0, 0.0);
+        }
+
+        // Clamp to the unit circle, the corners of the range are further.
+        let scale = self.shape(distance.min(1.0), size) / distance;
+
+        (x * scale, y * scale)
+    }
+
+    /// The output for a distance from the center.
+    fn shape(&self, distance: f32, size: f32) -> f32 {
+        if distance <= size {
+            return 0.0;
+        }
+
+        let value = if self.outer > size {
+            ((distance - size) / (self.outer - size)).min(1.0)
+        } else {
+            1.0
+        };
+
+        self.anti + (1.0 - self.anti) * self.curve.apply(value)
+    }
+}
+
+/// Processing of the stick and the pads of input states.
+#[derive(Clone, PartialEq, Default, Debug)]
+pub struct Processing {
+    /// The processing of the stick.
+    pub stick: Processor,
+
+    /// The processing of the left pad, if any.
+    pub left: Option<Processor>,
+
+    /// The processing of the right pad, if any.
+    pub right: Option<Processor>,
+}
+
+impl Processing {
+    /// Process the state, other states than `State::Input` are returned as
+    /// they are.
+    pub fn apply(&self, state: &State) -> State {
+        let mut state = *state;
+
+        if let State::Input { ref mut stick, ref mut pad, .. } = state {
+            *stick = self.stick.axis(*stick);
+
+            if let Some(left) = &self.left {
+                pad.left = left.axis(pad.left);
+            }
+
+            if let Some(right) = &self.right {
+                pad.right = right.axis(pad.right);
+            }
+        }
+
+        state
+    }
+}
+
+/// How far a trigger is pulled.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Level {
+    /// Below the activation point.
+    Released,
+
+    /// Past the activation point.
+    Soft,
+
+    /// All the way.
+    Full,
+}
+
+/// The outcome of a state for a trigger.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Press {
+    /// The pressure after the deadzones, from `0.0` to `1.0`.
+    pub value: f32,
+
+    /// The level for this state.
+    pub level: Level,
+
+    /// The level for the previous state.
+    pub previous: Level,
+
+    /// The haptic pulse to send, if any.
+    pub pulse: Option<Pulse>,
+}
+
+impl Press {
+    /// Check if the level changed with this state.
+    pub fn is_changed(&self) -> bool {
+        self.level != self.previous
+    }
+}
+
+/// Processing of a trigger.
+///
+/// The pressure goes through the deadzones and is split in levels, the soft
+/// pull being past the activation point and the full pull either past the
+/// full point or when the controller reports the trigger as clicked.
+///
+/// In hair trigger mode the soft pull ends as soon as the trigger is released
+/// by the given distance from the deepest point, and starts again when pulled
+/// by the same distance, without going back to the activation point.
+pub struct Pull {
+    side: Side,
+    deadzone: f32,
+    outer: f32,
+    activation: f32,
+    full: f32,
+    hair: Option<f32>,
+    pulse: Option<Pulse>,
+
+    level: Level,
+    extreme: f32,
+    held: bool,
+}
+
+impl Pull {
+    /// Create a processor for the trigger on the given side, activating at
+    /// half the pull.
+    pub fn new(side: Side) -> Pull {
+        Pull {
+            side,
+            deadzone: 0.0,
+            outer: 1.0,
+            activation: 0.5,
+            full: 1.0,
+            hair: None,
+            pulse: None,
+
+            level: Level::Released,
+            extreme: 0.0,
+            held: false,
+        }
+    }
+
+    /// Change the pressure ignored at the start of the pull.
+    pub fn deadzone(&mut self, value: f32) {
+        self.deadzone = value;
+    }
+
+    /// Change the pressure past which the output is saturated.
+    pub fn outer(&mut self, value: f32) {
+        self.outer = value;
+    }
+
+    /// Change the activation point of the soft pull, after the deadzones.
+    pub fn activation(&mut self, value: f32) {
+        self.activation = value;
+    }
+
+    /// Change the point of the full pull, after the deadzones.
+    pub fn full(&mut self, value: f32) {
+        self.full = value;
+    }
+
+    /// Enable hair trigger mode with the given distance, `None` disables it.
+    pub fn hair(&mut self, distance: Option<f32>) {
+        self.hair = distance;
+    }
+
+    /// Produce the pulse on the trigger side when reaching a deeper level,
+    /// `None` disables it.
+    pub fn pulse(&mut self, value: Option<Pulse>) {
+        self.pulse = value;
+    }
+
+    /// The current level.
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    /// Handle a state, other states than `State::Input` keep the current
+    /// level.
+    pub fn update(&mut self, state: &State) -> Press {
+        let State::Input { buttons, trigger, .. } = *state else {
+            return Press {
+                value: 0.0,
+                level: self.level,
+                previous: self.level,
+                pulse: None,
+            };
+        };
+
+        let raw = trigger.get(self.side);
+        let value = if raw <= self.deadzone {
+            0.0
+        } else if self.outer > self.deadzone {
+            ((raw - self.deadzone) / (self.outer - self.deadzone)).min(1.0)
+        } else {
+            1.0
+        };
+
+        let soft = self.soft(value);
+        let level = if value >= self.full || buttons.contains(self.side.trigger()) {
+            Level::Full
+        } else if soft {
+            Level::Soft
+        } else {
+            Level::Released
+        };
+
+        let previous = self.level;
+        self.level = level;
+
+        let pulse = if level > previous {
+            self.pulse.map(|pulse| Pulse { left: self.side == Side::Left, .. pulse })
+        } else {
+            None
+        };
+
+        Press { value, level, previous, pulse }
+    }
+
+    fn soft(&mut self, value: f32) -> bool {
+        let active = self.level != Level::Released;
+
+        if value == 0.0 {
+            self.held = false;
+            self.extreme = 0.0;
+
+            return false;
+        }
+
+        let Some(distance) = self.hair else {
+            return value >= self.activation;
+        };
+
+        // Follow the deepest point while active and the shallowest one while
+        // released, switching when moving away from it by the distance.
+        let now = if active {
+            self.extreme = self.extreme.max(value);
+            value > self.extreme - distance
+        } else if self.held {
+            self.extreme = self.extreme.min(value);
+            value >= self.extreme + distance
+        } else {
+            value >= self.activation
+        };
+
+        if now != active {
+            self.extreme = value;
+            self.held = true;
+        }
+
+        now
+    }
+}
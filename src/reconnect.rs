@@ -0,0 +1,168 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{Controller, Manager, State, Error, Result};
+use crate::controller::Settings;
+
+const RETRY: Duration = Duration::from_secs(1);
+
+/// Events from a reconnecting controller.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Event {
+    /// A state read from the controller.
+    State(State),
+
+    /// The controller went away, it will be looked for again on the following
+    /// calls.
+    Disconnected,
+
+    /// The controller came back and its settings have been restored.
+    Reconnected,
+}
+
+/// Controller wrapper reopening the device when it goes away.
+///
+/// The controller is identified by its serial number, so it's found again
+/// whether it comes back on the same dongle slot, another slot or wired. The
+/// lizard, sensors, timeout and LED settings are restored on reconnection, as
+/// is strict parsing.
+///
+/// The serial number is read once a controller is connected, a wireless slot
+/// can be empty when wrapped. Until it's known, the first connected
+/// controller is taken on reconnection.
+pub struct Reconnecting {
+    manager: Manager,
+    serial: Option<[u8; 10]>,
+    controller: Option<Controller>,
+    settings: Settings,
+
+    retry: Duration,
+    attempted: Option<Instant>,
+}
+
+impl Reconnecting {
+    /// Wrap an open controller.
+    pub fn new(manager: Manager, mut controller: Controller) -> Reconnecting {
+        let settings = *controller.settings();
+
+        Reconnecting {
+            manager,
+            serial: None,
+            controller: Some(controller),
+            settings,
+
+            retry: RETRY,
+            attempted: None,
+        }
+    }
+
+    /// The serial number the controller is identified by, once known.
+    pub fn serial(&self) -> Option<&[u8; 10]> {
+        self.serial.as_ref()
+    }
+
+    /// Change how often the device is looked for while it's away.
+    pub fn retry(&mut self, value: Duration) {
+        self.retry = value;
+    }
+
+    /// Check if the controller is currently available.
+    pub fn is_available(&self) -> bool {
+        self.controller.is_some()
+    }
+
+    /// Get the controller, if it's currently available.
+    pub fn controller(&mut self) -> Option<&mut Controller> {
+        self.controller.as_mut()
+    }
+
+    fn reopen(&mut self) -> Result<()> {
+        self.attempted = Some(Instant::now());
+
+        let mut controller = match self.serial {
+            Some(serial) => self.manager.open_serial(&serial)?,
+            None => self.manager.open()?,
+        };
+
+        *controller.settings() = self.settings;
+        controller.reset()?;
+
+        self.controller = Some(controller);
+
+        Ok(())
+    }
+
+    /// Get the next event, waiting up to `timeout`.
+    ///
+    /// While the controller is away this returns `Error::Timeout` once the
+    /// timeout expires without it coming back.
+    pub fn state(&mut self, timeout: Duration) -> Result<Event> {
+        let deadline = Instant::now() + timeout;
+
+        let Some(controller) = self.controller.as_mut() else {
+            loop {
+                let now = Instant::now();
+                let next = self.attempted.map_or(now, |at| at + self.retry);
+
+                if now >= next {
+                    match self.reopen() {
+                        Ok(()) => return Ok(Event::Reconnected),
+                        Err(Error::NotFound) => (),
+                        Err(e) if e.is_disconnected() => (),
+                        Err(e) => return Err(e),
+                    }
+
+                    continue;
+                }
+
+                if now >= deadline {
+                    return Err(Error::Timeout);
+                }
+
+                thread::sleep(deadline.min(next) - now);
+            }
+        };
+
+        let now = Instant::now();
+
+        let result = if self.serial.is_none() && self.attempted.is_none_or(|at| now >= at + self.retry) {
+            self.attempted = Some(now);
+            identify(controller).map(|serial| self.serial = serial)
+        } else {
+            Ok(())
+        };
+
+        match result.and_then(|()| controller.state(timeout)) {
+            Ok(state) => {
+                self.settings = *controller.settings();
+
+                Ok(Event::State(state))
+            }
+
+            Err(e) if e.is_disconnected() => {
+                self.settings = *controller.settings();
+                self.controller = None;
+                self.attempted = None;
+
+                Ok(Event::Disconnected)
+            }
+
+            Err(e) => {
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Read the serial number of the controller if it's connected.
+fn identify(controller: &mut Controller) -> Result<Option<[u8; 10]>> {
+    if !controller.is_connected() {
+        return Ok(None);
+    }
+
+    match controller.details() {
+        Ok(details) => Ok(Some(details.serial.controller)),
+        Err(e) if e.is_disconnected() => Err(e),
+        Err(_) => Ok(None),
+    }
+}
@@ -0,0 +1,160 @@
+//! Fixed offset report parsing.
+//!
+//! Works on plain byte slices without allocating, so it's available without
+//! the `std` feature.
+
+use crate::{Button, Error, Result};
+use crate::{State, Axis, Trigger, Pad, Angles, Quaternion, Battery};
+
+/// The size of a report, header included.
+pub const SIZE: usize = 64;
+
+/// The size of the header before the payload.
+pub const HEADER: usize = 4;
+
+/// Minimum payload size of input reports.
+const INPUT: usize = 44;
+
+/// Minimum payload size of power reports.
+const POWER: usize = 1;
+
+/// Minimum payload size of status reports.
+const STATUS: usize = 11;
+
+fn le_u16(payload: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([payload[at], payload[at + 1]])
+}
+
+fn le_i16(payload: &[u8], at: usize) -> i16 {
+    i16::from_le_bytes([payload[at], payload[at + 1]])
+}
+
+fn le_u32(payload: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([payload[at], payload[at + 1], payload[at + 2], payload[at + 3]])
+}
+
+fn axis(payload: &[u8], at: usize) -> Axis {
+    Axis {
+        x: le_i16(payload, at),
+        y: le_i16(payload, at + 2),
+    }
+}
+
+fn trigger(precise: u16, coarse: u8) -> f32 {
+    if precise != 0 {
+        precise as f32 / i16::MAX as f32
+    } else {
+        coarse as f32 / u8::MAX as f32
+    }
+}
+
+fn check(payload: &[u8], size: usize) -> Result<()> {
+    if payload.len() < size {
+        return Err(Error::Malformed("truncated report"));
+    }
+
+    Ok(())
+}
+
+/// Parse a full report, header included.
+pub fn parse(report: &[u8; SIZE]) -> Result<State> {
+    let end = (HEADER + report[3] as usize).min(SIZE);
+
+    parse_payload(report[2], &report[HEADER..end])
+}
+
+/// Parse the payload of a report with the given type.
+pub fn parse_payload(id: u8, payload: &[u8]) -> Result<State> {
+    match id {
+        0x01 => {
+            check(payload, INPUT)?;
+
+            // The buttons are big endian and share their last byte with the
+            // left trigger.
+            let buttons = Button::from_bits_retain(
+                u32::from_be_bytes([0, payload[4], payload[5], payload[6]]));
+
+            // The stick and the left pad share the same fields.
+            let left = axis(payload, 12);
+
+            let (lpad, stick) = if buttons.contains(Button::PAD_TOUCH) {
+                (left, Axis::default())
+            } else {
+                (Axis::default(), left)
+            };
+
+            Ok(State::Input {
+                sequence: le_u32(payload, 0),
+
+                buttons,
+
+                trigger: Trigger {
+                    left: trigger(le_u16(payload, 20), payload[7]),
+                    right: trigger(le_u16(payload, 22), payload[8]),
+                },
+
+                pad: Pad {
+                    left: lpad,
+                    right: axis(payload, 16),
+                },
+
+                stick,
+
+                gyro: Angles {
+                    pitch: le_i16(payload, 30),
+                    yaw: le_i16(payload, 32),
+                    roll: le_i16(payload, 34),
+                },
+
+                acceleration: Angles {
+                    pitch: le_i16(payload, 24),
+                    yaw: le_i16(payload, 26),
+                    roll: le_i16(payload, 28),
+                },
+
+                quaternion: Quaternion {
+                    w: le_i16(payload, 36),
+                    x: le_i16(payload, 38),
+                    y: le_i16(payload, 40),
+                    z: le_i16(payload, 42),
+                },
+            })
+        }
+
+        0x03 => {
+            check(payload, POWER)?;
+
+            Ok(State::Power(match payload[0] {
+                0x01 => false,
+                0x02 => true,
+
+                _ =>
+                    return Err(Error::Malformed("unknown power event"))
+            }))
+        }
+
+        0x04 => {
+            check(payload, STATUS)?;
+
+            Ok(State::Status {
+                sequence: le_u32(payload, 0),
+
+                battery: Battery {
+                    voltage: le_u16(payload, 8),
+                    percent: payload[10],
+                },
+            })
+        }
+
+        _ => {
+            let size = payload.len().min(SIZE - HEADER);
+            let mut copy = [0u8; SIZE - HEADER];
+            copy[..size].copy_from_slice(&payload[..size]);
+
+            Ok(State::Unknown {
+                id,
+                payload: copy,
+            })
+        }
+    }
+}
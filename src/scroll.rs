@@ -0,0 +1,164 @@
+//! Trackpad scroll wheel.
+
+use std::f32::consts::{PI, TAU};
+use crate::{Axis, Pulse, Side, State, Result};
+use crate::uinput::{Event, Sink};
+use crate::uinput::code::*;
+#[cfg(target_os = "linux")]
+use crate::uinput::Device;
+
+/// Default detents per revolution.
+const DETENTS: u16 = 20;
+
+/// Default distance from the center under which motion is ignored, as a
+/// fraction of the pad radius.
+const CENTER: f32 = 0.3;
+
+/// High resolution units per detent, as defined by the kernel.
+const RESOLUTION: i32 = 120;
+
+/// Scroll wheel driven by circling a finger on a trackpad.
+///
+/// Going clockwise scrolls down and counterclockwise scrolls up. The smooth
+/// motion is emitted as high resolution wheel events, and a wheel step is
+/// emitted with an optional pulse for every detent crossed.
+pub struct Scroll<S: Sink> {
+    sink: S,
+    side: Side,
+    detents: u16,
+    center: f32,
+    pulse: Option<Pulse>,
+
+    last: Option<f32>,
+    remainder: f32,
+    progress: i32,
+    events: Vec<Event>,
+}
+
+#[cfg(target_os = "linux")]
+impl Scroll<Device> {
+    /// Create a uinput device with a wheel.
+    pub fn create(name: &str, side: Side) -> Result<Scroll<Device>> {
+        let device = Device::new(name, &[], &[REL_WHEEL, REL_WHEEL_HI_RES], &[])?;
+
+        Ok(Scroll::new(device, side))
+    }
+}
+
+impl<S: Sink> Scroll<S> {
+    /// Create a scroll wheel on the given pad emitting to the given sink.
+    pub fn new(sink: S, side: Side) -> Scroll<S> {
+        Scroll {
+            sink,
+            side,
+            detents: DETENTS,
+            center: CENTER,
+            pulse: None,
+
+            last: None,
+            remainder: 0.0,
+            progress: 0,
+            events: Vec::new(),
+        }
+    }
+
+    /// The sink events are emitted to.
+    pub fn sink(&mut self) -> &mut S {
+        &mut self.sink
+    }
+
+    /// Change the amount of detents in a full revolution.
+    pub fn detents(&mut self, value: u16) {
+        self.detents = value;
+    }
+
+    /// Change the radius of the center region where motion is ignored, as a
+    /// fraction of the pad radius.
+    pub fn center(&mut self, value: f32) {
+        self.center = value;
+    }
+
+    /// Produce the pulse for every detent crossed, `None` disables it.
+    pub fn pulse(&mut self, value: Option<Pulse>) {
+        self.pulse = value;
+    }
+
+    /// Handle a state, other states than `State::Input` are ignored.
+    ///
+    /// Returns the haptic clicks to send, if any.
+    pub fn update(&mut self, state: &State) -> Result<Option<Pulse>> {
+        let State::Input { buttons, pad, .. } = *state else {
+            return Ok(None);
+        };
+
+        let angle = if buttons.contains(self.side.touch()) {
+            angle(pad.get(self.side), self.center)
+        } else {
+            None
+        };
+
+        let Some(angle) = angle else {
+            // Start over from the next touch.
+            self.last = None;
+            self.remainder = 0.0;
+            self.progress = 0;
+
+            return Ok(None);
+        };
+
+        let Some(last) = self.last.replace(angle) else {
+            return Ok(None);
+        };
+
+        if self.detents == 0 {
+            return Ok(None);
+        }
+
+        // Take the shortest way around, counterclockwise scrolling up.
+        let delta = (last - angle + PI).rem_euclid(TAU) - PI;
+        let units = delta / TAU * self.detents as f32 * RESOLUTION as f32;
+
+        self.events.clear();
+
+        self.remainder += units;
+        let smooth = self.remainder.round();
+        self.remainder -= smooth;
+
+        if smooth != 0.0 {
+            self.events.push(Event::relative(REL_WHEEL_HI_RES, smooth as i32));
+        }
+
+        // Whole detents are counted from the high resolution units so both
+        // always agree.
+        self.progress += smooth as i32;
+        let steps = self.progress / RESOLUTION;
+        self.progress %= RESOLUTION;
+
+        if steps != 0 {
+            self.events.push(Event::relative(REL_WHEEL, steps));
+        }
+
+        if !self.events.is_empty() {
+            self.events.push(Event::sync());
+            self.sink.emit(&self.events)?;
+        }
+
+        Ok(self.pulse.filter(|_| steps != 0).map(|pulse| Pulse {
+            count: (steps.unsigned_abs() * pulse.count as u32).min(u16::MAX as u32) as u16,
+            .. pulse
+        }))
+    }
+}
+
+/// The angle of the position clockwise from the top, if it's far enough from
+/// the center.
+fn angle(position: Axis, center: f32) -> Option<f32> {
+    let x = position.x as f32 / i16::MAX as f32;
+    let y = position.y as f32 / i16::MAX as f32;
+
+    if x.hypot(y) < center {
+        return None;
+    }
+
+    Some(x.atan2(y))
+}
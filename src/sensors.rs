@@ -1,12 +1,12 @@
 use crate::{Controller};
-use color_eyre::{Result};
+use crate::{Result};
 
 /// Controller sensors management.
 pub struct Sensors<'a> {
     controller: &'a mut Controller,
 }
 
-impl<'a, 'b> Sensors<'a> {
+impl<'a> Sensors<'a> {
     #[doc(hidden)]
     pub fn new(controller: &'a mut Controller) -> Sensors<'a> {
         Sensors {
@@ -1,10 +1,9 @@
-use std::{u8, i16};
-use std::io::{Read, Seek, SeekFrom};
-use byteorder::{ReadBytesExt, BigEndian, LittleEndian};
+#[cfg(feature = "std")]
+use std::io::Read;
 
-use crate::{Button};
-use color_eyre::{Result};
-use color_eyre::eyre::bail;
+use crate::{Button, Error, Result};
+#[cfg(feature = "std")]
+use crate::report;
 
 /// The controller state.
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -12,9 +11,13 @@ pub enum State {
     /// The controller is powering on or off.
     Power(bool),
 
-    Idle {
+    /// Periodic status of a wireless controller, sent while it's idle.
+    Status {
         /// Sequence number for the state.
         sequence: u32,
+
+        /// Battery state.
+        battery: Battery,
     },
 
     Input {
@@ -30,14 +33,41 @@ pub enum State {
         /// Pads state.
         pad: Pad,
 
-        /// Orientation of the controller if sensors are enabled.
-        orientation: Angles,
+        /// Analog stick state, zero when only `Button::PAD_TOUCH` is set
+        /// since the report then holds the left pad alone.
+        stick: Axis,
+
+        /// Angular velocity of the controller if sensors are enabled.
+        gyro: Angles,
 
         /// Acceleration of the controller if sensors are enabled.
         acceleration: Angles,
+
+        /// Orientation of the controller as computed by the firmware, if
+        /// sensors are enabled.
+        quaternion: Quaternion,
+    },
+
+    /// A report that isn't understood.
+    Unknown {
+        /// The report type.
+        id: u8,
+
+        /// The payload, padded with zeros.
+        payload: [u8; 60],
     },
 }
 
+/// The battery state of a wireless controller.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Battery {
+    /// The voltage in millivolts.
+    pub voltage: u16,
+
+    /// The charge left as estimated by the controller, in percent.
+    pub percent: u8,
+}
+
 /// The pressure on the triggers of the controller.
 #[derive(Clone, Copy, PartialEq, Default, Debug)]
 pub struct Trigger {
@@ -48,16 +78,71 @@ pub struct Trigger {
     pub right: f32,
 }
 
+impl Trigger {
+    /// The trigger on the given side.
+    pub fn get(&self, side: Side) -> f32 {
+        match side {
+            Side::Left => self.left,
+            Side::Right => self.right,
+        }
+    }
+}
+
 /// The pads of the controller.
 #[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
 pub struct Pad {
-    /// The left pad.
+    /// The left trackpad.
     pub left: Axis,
 
-    /// The right pad.
+    /// The right trackpad.
     pub right: Axis,
 }
 
+impl Pad {
+    /// The trackpad on the given side.
+    pub fn get(&self, side: Side) -> Axis {
+        match side {
+            Side::Left => self.left,
+            Side::Right => self.right,
+        }
+    }
+}
+
+/// A side of the controller.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "mapping", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "mapping", serde(rename_all = "snake_case"))]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    /// The button set while the pad on this side is touched.
+    pub fn touch(self) -> Button {
+        match self {
+            Side::Left => Button::PAD_TOUCH,
+            Side::Right => Button::TRACK_TOUCH,
+        }
+    }
+
+    /// The button set while the pad on this side is clicked.
+    pub fn click(self) -> Button {
+        match self {
+            Side::Left => Button::PAD,
+            Side::Right => Button::TRACK,
+        }
+    }
+
+    /// The button set while the trigger on this side is fully pulled.
+    pub fn trigger(self) -> Button {
+        match self {
+            Side::Left => Button::LEFT_TRIGGER,
+            Side::Right => Button::RIGHT_TRIGGER,
+        }
+    }
+}
+
 /// Axis on the pad.
 #[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
 pub struct Axis {
@@ -74,7 +159,9 @@ impl Axis {
     }
 }
 
-/// 3D position of the controller.
+/// Raw motion sensor values along the axes of the controller.
+///
+/// See the `motion` module for the units.
 #[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
 pub struct Angles {
     /// The pitch.
@@ -87,105 +174,91 @@ pub struct Angles {
     pub yaw: i16,
 }
 
+/// Raw orientation quaternion, each component ranging over the whole `i16`
+/// range.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Quaternion {
+    pub w: i16,
+    pub x: i16,
+    pub y: i16,
+    pub z: i16,
+}
+
 impl State {
     /// Parse the state from a given packet.
+    ///
+    /// The payload is read into a buffer on the stack and decoded by
+    /// `report::parse_payload`.
+    #[cfg(feature = "std")]
     #[inline]
-    pub fn parse<R: Read + Seek>(id: u8, mut buffer: R) -> Result<State> {
-        match id {
-            0x01 => {
-                let sequence = buffer.read_u32::<LittleEndian>()?;
-
-                let buttons = buffer.read_u32::<BigEndian>()?;
-                let ltrig = buttons & 0xff;
-                let buttons = buttons >> 8;
-                let rtrig = buffer.read_u8()?;
-
-                buffer.seek(SeekFrom::Current(3))?;
-
-                let lpad_x = buffer.read_i16::<LittleEndian>()?;
-                let lpad_y = buffer.read_i16::<LittleEndian>()?;
-                let rpad_x = buffer.read_i16::<LittleEndian>()?;
-                let rpad_y = buffer.read_i16::<LittleEndian>()?;
-
-                let ltrigp = buffer.read_u16::<LittleEndian>()?;
-                let rtrigp = buffer.read_u16::<LittleEndian>()?;
-
-                buffer.seek(SeekFrom::Current(8))?;
-
-                let apitch = buffer.read_i16::<LittleEndian>()?;
-                let ayaw = buffer.read_i16::<LittleEndian>()?;
-                let aroll = buffer.read_i16::<LittleEndian>()?;
-
-                let opitch = buffer.read_i16::<LittleEndian>()?;
-                let oyaw = buffer.read_i16::<LittleEndian>()?;
-                let oroll = buffer.read_i16::<LittleEndian>()?;
-
-                Ok(State::Input {
-                    sequence,
-
-                    buttons: Button::from_bits(buttons).ok_or(rusb::Error::InvalidParam)?,
-
-                    trigger: Trigger {
-                        left: if ltrigp != 0 {
-                            ltrigp as f32 / i16::MAX as f32
-                        } else {
-                            ltrig as f32 / u8::MAX as f32
-                        },
-
-                        right: if rtrigp != 0 {
-                            rtrigp as f32 / i16::MAX as f32
-                        } else {
-                            rtrig as f32 / u8::MAX as f32
-                        },
-                    },
-
-                    pad: Pad {
-                        left: Axis {
-                            x: lpad_x,
-                            y: lpad_y,
-                        },
-
-                        right: Axis {
-                            x: rpad_x,
-                            y: rpad_y,
-                        },
-                    },
-
-                    orientation: Angles {
-                        roll: oroll,
-                        pitch: opitch,
-                        yaw: oyaw,
-                    },
-
-                    acceleration: Angles {
-                        roll: aroll,
-                        pitch: apitch,
-                        yaw: ayaw,
-                    },
-				})
+    pub fn parse<R: Read>(id: u8, mut buffer: R) -> Result<State> {
+        let mut payload = [0u8; report::SIZE - report::HEADER];
+        let mut size = 0;
+
+        while size < payload.len() {
+            match buffer.read(&mut payload[size..])? {
+                0 => break,
+                read => size += read,
             }
+        }
 
-            0x03 => {
-                let mode = buffer.read_u8()?;
+        report::parse_payload(id, &payload[..size])
+    }
 
-                Ok(State::Power(match mode {
-                    0x01 => false,
-                    0x02 => true,
+    /// Check the state only contains known reports and buttons, as done by
+    /// the controller in strict mode.
+    pub fn validate(&self) -> Result<()> {
+        match *self {
+            State::Input { buttons, .. } if buttons.unknown() != 0 =>
+                Err(Error::UnknownButtons(buttons.bits())),
 
-                    _ =>
-                        bail!(rusb::Error::InvalidParam)
-                }))
-            }
+            State::Unknown { id, .. } =>
+                Err(Error::UnknownReport(id)),
 
-            0x04 => {
-                let sequence = buffer.read_u32::<LittleEndian>()?;
+            _ =>
+                Ok(()),
+        }
+    }
+}
 
-                Ok(State::Idle {
-                    sequence,
-                })
+/// Tracks the stick and the left pad across reports.
+///
+/// The stick and the left pad share the same fields in the input report,
+/// `Button::PAD_TOUCH` tells they are the left pad values and
+/// `Button::PAD_AND_STICK` that both are in use, in which case the reports
+/// alternate between the two. The tracker keeps the last known value of the
+/// source missing from each report and sets `Button::PAD_TOUCH` as long as
+/// the left pad is touched. With only `Button::PAD_TOUCH` set the stick is at
+/// rest, and stays zero.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Tracker {
+    stick: Axis,
+    left: Axis,
+}
+
+impl Tracker {
+    /// Create a new tracker.
+    pub fn new() -> Tracker {
+        Tracker::default()
+    }
+
+    /// Fill in the state from the previous reports.
+    pub fn update(&mut self, mut state: State) -> State {
+        if let State::Input { ref mut buttons, ref mut pad, ref mut stick, .. } = state {
+            if buttons.contains(Button::PAD_AND_STICK) {
+                if buttons.contains(Button::PAD_TOUCH) {
+                    *stick = self.stick;
+                } else {
+                    pad.left = self.left;
+                }
+
+                buttons.insert(Button::PAD_TOUCH);
             }
 
-            _ => bail!(rusb::Error::InvalidParam)
+            self.stick = *stick;
+            self.left = pad.left;
         }
+
+        state
     }
 }
@@ -0,0 +1,308 @@
+use std::collections::VecDeque;
+use std::sync::{mpsc, Arc, Condvar, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use arc_swap::ArcSwapOption;
+
+use crate::{Controller, Details, Feedback, Sound, State, Error, Result};
+use crate::worker::{self, Command};
+
+/// How many snapshots a subscriber can lag behind before the oldest ones are
+/// dropped for it.
+const CAPACITY: usize = 64;
+
+/// A state along with when it was received.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Snapshot {
+    /// The state read from the controller.
+    pub state: State,
+
+    /// The number of states received before this one.
+    pub sequence: u64,
+
+    /// When the report was read.
+    pub received: Instant,
+}
+
+struct Shared {
+    latest: ArcSwapOption<Snapshot>,
+    subscribers: Mutex<Vec<Arc<Channel>>>,
+    running: AtomicBool,
+}
+
+#[derive(Default)]
+struct Backlog {
+    snapshots: VecDeque<Snapshot>,
+    closed: bool,
+}
+
+#[derive(Default)]
+struct Channel {
+    backlog: Mutex<Backlog>,
+    ready: Condvar,
+}
+
+impl Channel {
+    fn backlog(&self) -> MutexGuard<'_, Backlog> {
+        self.backlog.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn push(&self, snapshot: Snapshot) {
+        let mut backlog = self.backlog();
+
+        if backlog.snapshots.len() == CAPACITY {
+            backlog.snapshots.pop_front();
+        }
+
+        backlog.snapshots.push_back(snapshot);
+        self.ready.notify_all();
+    }
+
+    fn close(&self) {
+        self.backlog().closed = true;
+        self.ready.notify_all();
+    }
+}
+
+/// The states following a subscription.
+///
+/// Up to 64 snapshots are kept, the oldest ones are dropped for a subscriber
+/// lagging further behind so the latest are always there.
+pub struct Subscription {
+    channel: Arc<Channel>,
+}
+
+impl Subscription {
+    /// Get the next snapshot if there's one already.
+    pub fn try_recv(&self) -> Option<Snapshot> {
+        self.channel.backlog().snapshots.pop_front()
+    }
+
+    /// Wait for the next snapshot.
+    ///
+    /// Fails with `Error::Disconnected` once the controller is disconnected
+    /// and every snapshot has been received.
+    pub fn recv(&self) -> Result<Snapshot> {
+        let mut backlog = self.channel.backlog();
+
+        loop {
+            if let Some(snapshot) = backlog.snapshots.pop_front() {
+                return Ok(snapshot);
+            }
+
+            if backlog.closed {
+                return Err(Error::Disconnected);
+            }
+
+            backlog = self.channel.ready.wait(backlog).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// Wait up to `timeout` for the next snapshot, like `recv`.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<Snapshot> {
+        let deadline = Instant::now() + timeout;
+        let mut backlog = self.channel.backlog();
+
+        loop {
+            if let Some(snapshot) = backlog.snapshots.pop_front() {
+                return Ok(snapshot);
+            }
+
+            if backlog.closed {
+                return Err(Error::Disconnected);
+            }
+
+            let now = Instant::now();
+
+            if now >= deadline {
+                return Err(Error::Timeout);
+            }
+
+            backlog = self.channel.ready.wait_timeout(backlog, deadline - now)
+                .unwrap_or_else(|e| e.into_inner()).0;
+        }
+    }
+}
+
+impl Iterator for Subscription {
+    type Item = Snapshot;
+
+    fn next(&mut self) -> Option<Snapshot> {
+        self.recv().ok()
+    }
+}
+
+/// Controller handle with the input read on a background thread.
+///
+/// The most recent state can be looked at from any thread without locking,
+/// while commands are queued and run by the reader thread between reads. The
+/// handle can be cloned and shared, the thread stops once every clone is
+/// dropped or the controller is disconnected.
+///
+/// Commands are run between reads, see the `worker` module for how long they
+/// can wait.
+///
+/// Reports that fail to parse are skipped.
+#[derive(Clone)]
+pub struct Threaded {
+    commands: mpsc::Sender<Command>,
+    shared: Arc<Shared>,
+}
+
+impl Threaded {
+    /// Move the controller to a background reader thread.
+    pub fn new(controller: Controller) -> Threaded {
+        let shared = Arc::new(Shared {
+            latest: ArcSwapOption::empty(),
+            subscribers: Mutex::new(Vec::new()),
+            running: AtomicBool::new(true),
+        });
+
+        let publisher = shared.clone();
+        let mut sequence = 0;
+
+        let commands = worker::spawn(controller, move |result| {
+            match result {
+                Ok(state) => {
+                    let snapshot = Snapshot {
+                        state,
+                        sequence,
+                        received: Instant::now(),
+                    };
+
+                    sequence += 1;
+                    publisher.latest.store(Some(Arc::new(snapshot)));
+
+                    // Subscriptions that were dropped only have this reference
+                    // left.
+                    publisher.subscribers.lock().unwrap().retain(|channel| {
+                        channel.push(snapshot);
+                        Arc::strong_count(channel) > 1
+                    });
+                }
+
+                Err(e) if e.is_disconnected() => {
+                    publisher.running.store(false, Ordering::SeqCst);
+
+                    for channel in publisher.subscribers.lock().unwrap().drain(..) {
+                        channel.close();
+                    }
+                }
+
+                Err(_) => (),
+            }
+
+            true
+        });
+
+        Threaded {
+            commands,
+            shared,
+        }
+    }
+
+    /// Check if the reader thread is still running.
+    pub fn is_running(&self) -> bool {
+        self.shared.running.load(Ordering::SeqCst)
+    }
+
+    /// The most recent state, if any has been received.
+    pub fn latest(&self) -> Option<Snapshot> {
+        self.shared.latest.load().as_deref().copied()
+    }
+
+    /// Receive every following state.
+    ///
+    /// The oldest states are dropped for a subscriber lagging too far behind,
+    /// the subscription ends once the controller is disconnected.
+    pub fn subscribe(&self) -> Subscription {
+        let channel = Arc::new(Channel::default());
+        let mut subscribers = self.shared.subscribers.lock().unwrap();
+
+        if self.is_running() {
+            subscribers.push(channel.clone());
+        } else {
+            channel.close();
+        }
+
+        Subscription {
+            channel,
+        }
+    }
+
+    /// Queue the function to be run with the controller, without waiting for
+    /// it.
+    pub fn queue<F>(&self, func: F) -> Result<()>
+        where F: FnOnce(&mut Controller) + Send + 'static
+    {
+        self.commands.send(Box::new(func)).map_err(|_| Error::Disconnected)
+    }
+
+    /// Run the function with the controller, waiting for its result.
+    ///
+    /// Fails with `Error::Disconnected` if the reader thread has stopped.
+    pub fn execute<T, F>(&self, func: F) -> Result<T>
+        where T: Send + 'static,
+              F: FnOnce(&mut Controller) -> Result<T> + Send + 'static
+    {
+        let (sender, receiver) = mpsc::sync_channel(1);
+
+        self.queue(move |controller| {
+            let _ = sender.send(func(controller));
+        })?;
+
+        receiver.recv().map_err(|_| Error::Disconnected)?
+    }
+
+    /// Get the controller details.
+    pub fn details(&self) -> Result<Details> {
+        self.execute(|controller| controller.details())
+    }
+
+    /// Send feedback built by the given function.
+    pub fn feedback<F>(&self, build: F) -> Result<()>
+        where F: FnOnce(Feedback<'_>) -> Feedback<'_> + Send + 'static
+    {
+        self.execute(|controller| build(controller.feedback()).send())
+    }
+
+    /// Play a sound with the given function.
+    pub fn sound<F>(&self, play: F) -> Result<()>
+        where F: FnOnce(Sound<'_>) -> Result<()> + Send + 'static
+    {
+        self.execute(|controller| play(controller.sound()))
+    }
+
+    /// Set the LED brightness.
+    pub fn led(&self, value: u8) -> Result<()> {
+        self.execute(move |controller| controller.led().level(value))
+    }
+
+    /// Enable or disable the sensors.
+    pub fn sensors(&self, enable: bool) -> Result<()> {
+        self.execute(move |controller| if enable {
+            controller.sensors().on()
+        } else {
+            controller.sensors().off()
+        })
+    }
+
+    /// Enable or disable lizard mode.
+    pub fn lizard(&self, enable: bool) -> Result<()> {
+        self.execute(move |controller| if enable {
+            controller.lizard().enable()
+        } else {
+            controller.lizard().disable()
+        })
+    }
+
+    /// Set the idle timeout.
+    pub fn timeout(&self, value: Duration) -> Result<()> {
+        self.execute(move |controller| controller.timeout(value))
+    }
+
+    /// Turn the controller off.
+    pub fn off(&self) -> Result<()> {
+        self.execute(|controller| controller.off())
+    }
+}
@@ -0,0 +1,51 @@
+//! Transports used to talk to the controller.
+
+use std::time::Duration;
+use crate::{Result};
+
+pub mod usb;
+
+pub use usb::Usb;
+
+#[cfg(target_os = "linux")]
+pub mod hidraw;
+
+#[cfg(target_os = "linux")]
+pub use hidraw::Hidraw;
+
+pub mod mock;
+
+pub use mock::Mock;
+
+pub mod record;
+
+pub use record::{Recorder, Replay};
+
+/// Low level access to a controller.
+///
+/// A transport moves full packets around, the framing of the payload is left
+/// to the `Controller`.
+pub trait Transport: Send {
+    /// Send a feature report.
+    fn send_feature(&mut self, packet: &[u8]) -> Result<()>;
+
+    /// Get a feature report, returning the amount of bytes read.
+    fn get_feature(&mut self, packet: &mut [u8]) -> Result<usize>;
+
+    /// Read an input report, returning the amount of bytes read.
+    fn read_input(&mut self, packet: &mut [u8], timeout: Duration) -> Result<usize>;
+}
+
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    fn send_feature(&mut self, packet: &[u8]) -> Result<()> {
+        (**self).send_feature(packet)
+    }
+
+    fn get_feature(&mut self, packet: &mut [u8]) -> Result<usize> {
+        (**self).get_feature(packet)
+    }
+
+    fn read_input(&mut self, packet: &mut [u8], timeout: Duration) -> Result<usize> {
+        (**self).read_input(packet, timeout)
+    }
+}
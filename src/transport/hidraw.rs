@@ -0,0 +1,238 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use crate::{Descriptor, Error, Result};
+use crate::{VENDOR_ID, PRODUCT_ID};
+use crate::controller::PACKET_MAX_SIZE;
+use crate::transport::Transport;
+
+const fn ioctl(nr: u8, size: usize) -> libc::Ioctl {
+    // _IOC(_IOC_WRITE | _IOC_READ, 'H', nr, size)
+    ((3 << 30) | (size << 16) | ((b'H' as usize) << 8) | nr as usize) as libc::Ioctl
+}
+
+const REPORT_SIZE: usize = PACKET_MAX_SIZE + 1;
+const HIDIOCSFEATURE: libc::Ioctl = ioctl(0x06, REPORT_SIZE);
+const HIDIOCGFEATURE: libc::Ioctl = ioctl(0x07, REPORT_SIZE);
+
+/// A hidraw device node.
+///
+/// Feature reports are prefixed by the report number, which is always `0` for
+/// the controller, while input reports are not.
+pub trait Node: Send {
+    /// Send a feature report.
+    fn set_feature(&mut self, report: &[u8]) -> io::Result<usize>;
+
+    /// Get a feature report.
+    fn get_feature(&mut self, report: &mut [u8]) -> io::Result<usize>;
+
+    /// Read an input report, failing with `ErrorKind::TimedOut` if none came
+    /// in time and with `EIO` once the device is gone. A zero timeout waits
+    /// forever.
+    fn read_input(&mut self, report: &mut [u8], timeout: Duration) -> io::Result<usize>;
+}
+
+fn check(value: libc::c_int) -> io::Result<usize> {
+    if value < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(value as usize)
+    }
+}
+
+impl Node for File {
+    fn set_feature(&mut self, report: &[u8]) -> io::Result<usize> {
+        check(unsafe { libc::ioctl(self.as_raw_fd(), HIDIOCSFEATURE, report.as_ptr()) })
+    }
+
+    fn get_feature(&mut self, report: &mut [u8]) -> io::Result<usize> {
+        check(unsafe { libc::ioctl(self.as_raw_fd(), HIDIOCGFEATURE, report.as_mut_ptr()) })
+    }
+
+    fn read_input(&mut self, report: &mut [u8], timeout: Duration) -> io::Result<usize> {
+        let mut fd = libc::pollfd {
+            fd: self.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+
+        let timeout = if timeout.is_zero() {
+            -1
+        } else {
+            timeout.as_millis().clamp(1, libc::c_int::MAX as u128) as libc::c_int
+        };
+
+        if check(unsafe { libc::poll(&mut fd, 1, timeout) })? == 0 {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+
+        // The node hangs up once the device is unplugged, reads would then
+        // fail with `EIO` as well.
+        if fd.revents & libc::POLLIN == 0 && fd.revents & (libc::POLLHUP | libc::POLLERR) != 0 {
+            return Err(io::Error::from_raw_os_error(libc::EIO));
+        }
+
+        self.read(report)
+    }
+}
+
+/// Transport going through a Linux hidraw device node.
+///
+/// Unlike the libusb transport the kernel drivers are left attached, so only
+/// read and write access to the node is needed.
+pub struct Hidraw<N: Node = File> {
+    node: N,
+    report: [u8; REPORT_SIZE],
+}
+
+impl Hidraw {
+    /// Open the given hidraw node, for example `/dev/hidraw3`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Hidraw> {
+        let file = OpenOptions::new().read(true).write(true).open(path).map_err(error)?;
+
+        Ok(Hidraw::new(file))
+    }
+}
+
+impl<N: Node> Hidraw<N> {
+    /// Create a transport over the given node.
+    pub fn new(node: N) -> Hidraw<N> {
+        Hidraw {
+            node,
+            report: [0u8; REPORT_SIZE],
+        }
+    }
+}
+
+fn error(value: io::Error) -> Error {
+    match value.raw_os_error() {
+        Some(libc::ENODEV) | Some(libc::ENOENT) =>
+            Error::Disconnected,
+
+        _ if value.kind() == io::ErrorKind::TimedOut =>
+            Error::Timeout,
+
+        _ =>
+            Error::Io(value),
+    }
+}
+
+impl<N: Node> Transport for Hidraw<N> {
+    fn send_feature(&mut self, packet: &[u8]) -> Result<()> {
+        let size = packet.len().min(PACKET_MAX_SIZE);
+
+        self.report[0] = 0x00;
+        self.report[1..].fill(0);
+        self.report[1..1 + size].copy_from_slice(&packet[..size]);
+        self.node.set_feature(&self.report).map_err(error)?;
+
+        Ok(())
+    }
+
+    fn get_feature(&mut self, packet: &mut [u8]) -> Result<usize> {
+        self.report.fill(0);
+
+        let size = self.node.get_feature(&mut self.report).map_err(error)?;
+        let size = size.saturating_sub(1).min(packet.len());
+        packet[..size].copy_from_slice(&self.report[1..1 + size]);
+
+        Ok(size)
+    }
+
+    fn read_input(&mut self, packet: &mut [u8], timeout: Duration) -> Result<usize> {
+        // Reads fail with `EIO` after an unplug rather than `ENODEV`.
+        let size = self.node.read_input(packet, timeout).map_err(|e| match e.raw_os_error() {
+            Some(libc::EIO) => Error::Disconnected,
+            _ => error(e),
+        })?;
+
+        if size == 0 {
+            return Err(Error::Disconnected);
+        }
+
+        Ok(size)
+    }
+}
+
+fn attribute(path: &Path, name: &str) -> Option<String> {
+    fs::read_to_string(path.join(name)).ok().map(|value| value.trim().to_owned())
+}
+
+/// Hidraw nodes of a device along with their interface number.
+pub(crate) type Nodes = Vec<(u16, PathBuf)>;
+
+/// Find the hidraw nodes of the controller devices through sysfs.
+pub(crate) fn scan() -> Result<Vec<(Nodes, Descriptor)>> {
+    let mut devices: Vec<(PathBuf, Nodes, Descriptor)> = Vec::new();
+
+    let Ok(entries) = fs::read_dir("/sys/class/hidraw") else {
+        return Ok(Vec::new());
+    };
+
+    for entry in entries.flatten() {
+
+        // .../usb1/1-2/1-2:1.2/0003:28DE:1102.0007
+        let Ok(hid) = fs::canonicalize(entry.path().join("device")) else {
+            continue;
+        };
+
+        let Some(uevent) = attribute(&hid, "uevent") else {
+            continue;
+        };
+
+        // HID_ID=0003:000028DE:00001102
+        let Some(id) = uevent.lines().find_map(|line| line.strip_prefix("HID_ID=")) else {
+            continue;
+        };
+
+        let mut parts = id.split(':').map(|part| u32::from_str_radix(part, 16).ok());
+        let (Some(Some(0x03)), Some(Some(vendor)), Some(Some(product))) = (parts.next(), parts.next(), parts.next()) else {
+            continue;
+        };
+
+        if vendor != VENDOR_ID as u32 || !PRODUCT_ID.contains(&(product as u16)) {
+            continue;
+        }
+
+        let Some(interface) = hid.parent() else {
+            continue;
+        };
+
+        let Some(usb) = interface.parent() else {
+            continue;
+        };
+
+        let number = attribute(interface, "bInterfaceNumber")
+            .and_then(|value| u16::from_str_radix(&value, 16).ok());
+
+        let bus = attribute(usb, "busnum").and_then(|value| value.parse().ok());
+        let address = attribute(usb, "devnum").and_then(|value| value.parse().ok());
+
+        let (Some(number), Some(bus), Some(address)) = (number, bus, address) else {
+            continue;
+        };
+
+        let node = Path::new("/dev").join(entry.file_name());
+
+        if let Some((_, nodes, _)) = devices.iter_mut().find(|(path, _, _)| path == usb) {
+            nodes.push((number, node));
+        } else {
+            devices.push((usb.to_owned(), vec![(number, node)], Descriptor {
+                bus,
+                address,
+                product: product as u16,
+                serial: attribute(usb, "serial"),
+            }));
+        }
+    }
+
+    devices.sort_by_key(|(_, _, d)| (d.bus, d.address));
+
+    Ok(devices.into_iter().map(|(_, mut nodes, descriptor)| {
+        nodes.sort();
+
+        (nodes, descriptor)
+    }).collect())
+}
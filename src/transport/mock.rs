@@ -0,0 +1,167 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use crate::{Error, Result};
+use crate::controller::{PACKET_MAX_SIZE, PACKET_START_IND};
+use crate::transport::Transport;
+
+/// In-memory transport answering like a controller would.
+///
+/// The mock is a cheap handle to shared state, so a clone can be kept around
+/// to script the device and inspect what has been sent after the original
+/// has been moved into a `Controller`.
+///
+/// By default it answers the build details (`0x83`), serial number (`0xae`),
+/// receiver information (`0xa1`) and connection (`0xb4`) requests.
+#[derive(Clone)]
+pub struct Mock {
+    inner: Arc<Mutex<Inner>>,
+}
+
+struct Response {
+    id: u8,
+    request: Vec<u8>,
+    payload: Vec<u8>,
+}
+
+struct Inner {
+    responses: Vec<Response>,
+    pending: Option<[u8; PACKET_MAX_SIZE]>,
+    input: VecDeque<[u8; PACKET_MAX_SIZE]>,
+    sent: Vec<[u8; PACKET_MAX_SIZE]>,
+}
+
+impl Default for Mock {
+    fn default() -> Self {
+        Mock::new()
+    }
+}
+
+impl Mock {
+    /// Create a new mock with the default answers.
+    pub fn new() -> Mock {
+        let mock = Mock {
+            inner: Arc::new(Mutex::new(Inner {
+                responses: Vec::new(),
+                pending: None,
+                input: VecDeque::new(),
+                sent: Vec::new(),
+            })),
+        };
+
+        mock.respond(0x83, &[], &[
+            0x09, 0x01, 0x00, 0x00, 0x00,
+            0x0a, 0x80, 0x6e, 0x45, 0x56,
+            0x04, 0x00, 0x59, 0x5d, 0x57,
+            0x05, 0x00, 0x3b, 0x4c, 0x56,
+        ]);
+        mock.respond(0xae, &[0x00], b"\x00MAINBOARD1");
+        mock.respond(0xae, &[0x01], b"\x01CONTROLLER");
+        mock.respond(0xa1, &[], b"\x56\x4c\x3b\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00RECEIVER01");
+        mock.connected(true);
+
+        mock
+    }
+
+    fn inner(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Answer requests with the given id whose payload starts with `request`.
+    ///
+    /// Later answers take precedence over earlier ones.
+    pub fn respond(&self, id: u8, request: &[u8], payload: &[u8]) {
+        self.inner().responses.push(Response {
+            id,
+            request: request.to_vec(),
+            payload: payload.to_vec(),
+        });
+    }
+
+    /// Change what the connection check answers.
+    pub fn connected(&self, value: bool) {
+        self.respond(0xb4, &[], &[if value { 0x02 } else { 0x01 }]);
+    }
+
+    /// Queue a raw input report.
+    pub fn push(&self, report: [u8; 64]) {
+        let mut packet = [0u8; PACKET_MAX_SIZE];
+        packet[PACKET_START_IND..PACKET_START_IND + 64].copy_from_slice(&report);
+
+        self.inner().input.push_back(packet);
+    }
+
+    /// Queue an input report with the given type and payload.
+    pub fn input(&self, id: u8, payload: &[u8]) {
+        let mut report = [0u8; 64];
+        report[0] = 0x01;
+        report[2] = id;
+        report[3] = payload.len() as u8;
+        report[4..4 + payload.len()].copy_from_slice(payload);
+
+        self.push(report);
+    }
+
+    /// Amount of input reports still queued.
+    pub fn remaining(&self) -> usize {
+        self.inner().input.len()
+    }
+
+    /// Feature reports sent so far, including the ones for requests.
+    pub fn sent(&self) -> Vec<[u8; PACKET_MAX_SIZE]> {
+        self.inner().sent.clone()
+    }
+
+    /// Forget the feature reports sent so far.
+    pub fn clear(&self) {
+        self.inner().sent.clear();
+    }
+}
+
+impl Transport for Mock {
+    fn send_feature(&mut self, packet: &[u8]) -> Result<()> {
+        let mut inner = self.inner();
+
+        let mut copy = [0u8; PACKET_MAX_SIZE];
+        let size = packet.len().min(PACKET_MAX_SIZE);
+        copy[..size].copy_from_slice(&packet[..size]);
+        inner.sent.push(copy);
+
+        let id = copy[PACKET_START_IND];
+        let payload = &copy[PACKET_START_IND + 2..];
+
+        let pending = inner.responses.iter().rev()
+            .find(|r| r.id == id && payload.starts_with(&r.request))
+            .map(|r| {
+                let mut answer = [0u8; PACKET_MAX_SIZE];
+                answer[PACKET_START_IND] = id;
+                answer[PACKET_START_IND + 1] = r.payload.len() as u8;
+                answer[PACKET_START_IND + 2..PACKET_START_IND + 2 + r.payload.len()].copy_from_slice(&r.payload);
+
+                answer
+            });
+
+        inner.pending = pending;
+
+        Ok(())
+    }
+
+    fn get_feature(&mut self, packet: &mut [u8]) -> Result<usize> {
+        let answer = self.inner().pending.take().unwrap_or([0u8; PACKET_MAX_SIZE]);
+        let size = packet.len().min(PACKET_MAX_SIZE);
+        packet[..size].copy_from_slice(&answer[..size]);
+
+        Ok(size)
+    }
+
+    fn read_input(&mut self, packet: &mut [u8], _timeout: Duration) -> Result<usize> {
+        let Some(report) = self.inner().input.pop_front() else {
+            return Err(Error::Timeout);
+        };
+
+        let size = packet.len().min(PACKET_MAX_SIZE);
+        packet[..size].copy_from_slice(&report[..size]);
+
+        Ok(size)
+    }
+}
@@ -0,0 +1,215 @@
+//! Recording and replay of the traffic with a controller.
+//!
+//! The log starts with the `SCLOG` magic and a version byte, followed by one
+//! entry per packet:
+//!
+//! - the kind, `0x01` for input reports, `0x02` for sent feature reports and
+//!   `0x03` for received feature reports,
+//! - the time since the recording started in microseconds, as a little endian
+//!   unsigned 64 bits integer,
+//! - the size of the packet as a byte, then the packet itself.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::{Error, Result};
+use crate::transport::Transport;
+
+const MAGIC: &[u8; 5] = b"SCLOG";
+const VERSION: u8 = 1;
+
+const INPUT: u8 = 0x01;
+const SENT: u8 = 0x02;
+const FEATURE: u8 = 0x03;
+
+/// Transport wrapper writing every packet going through it to a log.
+///
+/// Writes are not flushed, wrap the writer in a `BufWriter` to keep the
+/// overhead down and drop the recorder to flush it.
+pub struct Recorder<T: Transport, W: Write + Send> {
+    transport: T,
+    writer: W,
+    started: Instant,
+}
+
+impl<T: Transport, W: Write + Send> Recorder<T, W> {
+    /// Start recording the traffic going through the transport.
+    pub fn new(transport: T, mut writer: W) -> Result<Recorder<T, W>> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+
+        Ok(Recorder {
+            transport,
+            writer,
+            started: Instant::now(),
+        })
+    }
+
+    /// Stop recording, returning the transport and the writer.
+    pub fn into_inner(self) -> (T, W) {
+        (self.transport, self.writer)
+    }
+
+    fn write(&mut self, kind: u8, packet: &[u8]) -> Result<()> {
+        let time = self.started.elapsed().as_micros() as u64;
+        let size = packet.len().min(u8::MAX as usize);
+
+        self.writer.write_all(&[kind])?;
+        self.writer.write_all(&time.to_le_bytes())?;
+        self.writer.write_all(&[size as u8])?;
+        self.writer.write_all(&packet[..size])?;
+
+        Ok(())
+    }
+}
+
+impl<T: Transport, W: Write + Send> Transport for Recorder<T, W> {
+    fn send_feature(&mut self, packet: &[u8]) -> Result<()> {
+        self.transport.send_feature(packet)?;
+        self.write(SENT, packet)
+    }
+
+    fn get_feature(&mut self, packet: &mut [u8]) -> Result<usize> {
+        let size = self.transport.get_feature(packet)?;
+        self.write(FEATURE, &packet[..size])?;
+
+        Ok(size)
+    }
+
+    fn read_input(&mut self, packet: &mut [u8], timeout: Duration) -> Result<usize> {
+        let size = self.transport.read_input(packet, timeout)?;
+        self.write(INPUT, &packet[..size])?;
+
+        Ok(size)
+    }
+}
+
+/// Transport playing back a recorded log.
+///
+/// Input reports are returned with the same spacing as when they were
+/// recorded, divided by the speed, and received feature reports are returned
+/// in order. Sent feature reports are ignored. Once the input or the received
+/// feature reports run out the controller appears disconnected.
+pub struct Replay {
+    inputs: VecDeque<(Duration, Vec<u8>)>,
+    features: VecDeque<Vec<u8>>,
+    speed: f64,
+    started: Option<(Instant, Duration)>,
+}
+
+impl Replay {
+    /// Load the log at the given path.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Replay> {
+        Replay::new(BufReader::new(File::open(path)?))
+    }
+
+    /// Load a log from the reader.
+    pub fn new<R: Read>(mut reader: R) -> Result<Replay> {
+        let mut header = [0u8; 6];
+        reader.read_exact(&mut header)?;
+
+        if &header[..5] != MAGIC || header[5] != VERSION {
+            return Err(Error::Malformed("not a recording"));
+        }
+
+        let mut inputs = VecDeque::new();
+        let mut features = VecDeque::new();
+
+        loop {
+            let mut kind = [0u8; 1];
+
+            match reader.read_exact(&mut kind) {
+                Ok(()) => (),
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(Error::Io(e)),
+            }
+
+            let mut time = [0u8; 8];
+            let mut size = [0u8; 1];
+            reader.read_exact(&mut time)?;
+            reader.read_exact(&mut size)?;
+
+            let mut packet = vec![0u8; size[0] as usize];
+            reader.read_exact(&mut packet)?;
+
+            match kind[0] {
+                INPUT => inputs.push_back((Duration::from_micros(u64::from_le_bytes(time)), packet)),
+                FEATURE => features.push_back(packet),
+                SENT => (),
+                _ => return Err(Error::Malformed("unknown recording entry")),
+            }
+        }
+
+        Ok(Replay {
+            inputs,
+            features,
+            speed: 1.0,
+            started: None,
+        })
+    }
+
+    /// Change the playback speed, `2.0` plays twice as fast and
+    /// `f64::INFINITY` doesn't wait at all.
+    ///
+    /// Panics if the speed is zero, negative or NaN.
+    pub fn speed(mut self, value: f64) -> Replay {
+        assert!(value > 0.0, "invalid replay speed {}", value);
+
+        self.speed = value;
+        self
+    }
+
+    /// Amount of input reports left.
+    pub fn remaining(&self) -> usize {
+        self.inputs.len()
+    }
+}
+
+impl Transport for Replay {
+    fn send_feature(&mut self, _packet: &[u8]) -> Result<()> {
+        Ok(())
+    }
+
+    fn get_feature(&mut self, packet: &mut [u8]) -> Result<usize> {
+        let Some(answer) = self.features.pop_front() else {
+            return Err(Error::Disconnected);
+        };
+
+        let size = answer.len().min(packet.len());
+
+        packet.fill(0);
+        packet[..size].copy_from_slice(&answer[..size]);
+
+        Ok(packet.len())
+    }
+
+    fn read_input(&mut self, packet: &mut [u8], timeout: Duration) -> Result<usize> {
+        let Some(&(time, _)) = self.inputs.front() else {
+            return Err(Error::Disconnected);
+        };
+
+        let (instant, origin) = *self.started.get_or_insert((Instant::now(), time));
+        let due = instant + time.saturating_sub(origin).div_f64(self.speed);
+        let now = Instant::now();
+
+        if due > now {
+            let wait = due - now;
+
+            if !timeout.is_zero() && wait > timeout {
+                thread::sleep(timeout);
+                return Err(Error::Timeout);
+            }
+
+            thread::sleep(wait);
+        }
+
+        let (_, report) = self.inputs.pop_front().unwrap();
+        let size = report.len().min(packet.len());
+        packet[..size].copy_from_slice(&report[..size]);
+
+        Ok(size)
+    }
+}
@@ -0,0 +1,102 @@
+use std::sync::Arc;
+use std::time::Duration;
+use crate::{Error, Result};
+use crate::transport::Transport;
+
+/// Transport going through libusb.
+///
+/// A device can have several controller interfaces, as the wireless dongle
+/// does, in which case each transport talks to one of them while sharing the
+/// same handle.
+pub struct Usb {
+    handle: Arc<rusb::DeviceHandle<rusb::Context>>,
+    address: u8,
+    index: u16,
+}
+
+fn find_interfaces(
+    device: &rusb::Device<rusb::Context>,
+    handle: &rusb::DeviceHandle<rusb::Context>,
+    first: u16,
+) -> Result<Vec<(u16, u8)>> {
+    let mut found = Vec::new();
+
+    for i in 0..device.device_descriptor()?.num_configurations() {
+        for interface in device.config_descriptor(i)?.interfaces() {
+            if handle.kernel_driver_active(interface.number())? {
+                handle.detach_kernel_driver(interface.number())?;
+            }
+
+            for descriptor in interface.descriptors() {
+                if descriptor.class_code() != 3 ||
+                    descriptor.sub_class_code() != 0 ||
+                    descriptor.protocol_code() != 0
+                {
+                    continue;
+                }
+
+                handle.claim_interface(descriptor.interface_number())?;
+
+                if (descriptor.interface_number() as u16) < first {
+                    continue;
+                }
+
+                let address = descriptor.endpoint_descriptors()
+                    .find(|end| end.direction() == rusb::Direction::In &&
+                        end.transfer_type() == rusb::TransferType::Interrupt)
+                    .map(|end| end.address());
+
+                if let Some(address) = address {
+                    found.push((descriptor.interface_number() as u16, address));
+                }
+            }
+        }
+    }
+
+    found.sort();
+    found.dedup_by_key(|&mut (index, _)| index);
+
+    Ok(found)
+}
+
+impl Usb {
+    /// Claim the controller interfaces of the device, starting from interface
+    /// `first`, and return a transport for each of them in interface order.
+    pub fn open(device: rusb::Device<rusb::Context>, first: u16) -> Result<Vec<Usb>> {
+        let handle = device.open()?;
+        let found = find_interfaces(&device, &handle, first)?;
+
+        if found.is_empty() {
+            return Err(Error::Unsupported("missing interrupt endpoint"));
+        }
+
+        let handle = Arc::new(handle);
+
+        Ok(found.into_iter().map(|(index, address)| Usb {
+            handle: handle.clone(),
+            address,
+            index,
+        }).collect())
+    }
+
+    /// The interface the transport talks to.
+    pub fn interface(&self) -> u16 {
+        self.index
+    }
+}
+
+impl Transport for Usb {
+    fn send_feature(&mut self, packet: &[u8]) -> Result<()> {
+        self.handle.write_control(0x21, 0x09, 0x0300, self.index, packet, Duration::from_secs(0))?;
+
+        Ok(())
+    }
+
+    fn get_feature(&mut self, packet: &mut [u8]) -> Result<usize> {
+        Ok(self.handle.read_control(0xa1, 0x01, 0x0300, self.index, packet, Duration::from_secs(0))?)
+    }
+
+    fn read_input(&mut self, packet: &mut [u8], timeout: Duration) -> Result<usize> {
+        Ok(self.handle.read_interrupt(self.address, packet, timeout)?)
+    }
+}
@@ -0,0 +1,443 @@
+//! Virtual input devices.
+//!
+//! Events are handed to a `Sink`, which is a Linux uinput `Device` in
+//! practice, or a `Collector` keeping them in memory for testing.
+
+use std::sync::{Arc, Mutex, MutexGuard};
+use crate::{Button, State, Result};
+
+/// Event types and codes, as defined in `linux/input-event-codes.h`.
+pub mod code {
+    pub const EV_SYN: u16 = 0x00;
+    pub const EV_KEY: u16 = 0x01;
+    pub const EV_REL: u16 = 0x02;
+    pub const EV_ABS: u16 = 0x03;
+
+    pub const SYN_REPORT: u16 = 0x00;
+
+    pub const BTN_LEFT: u16 = 0x110;
+    pub const BTN_RIGHT: u16 = 0x111;
+    pub const BTN_MIDDLE: u16 = 0x112;
+
+    pub const BTN_SOUTH: u16 = 0x130;
+    pub const BTN_EAST: u16 = 0x131;
+    pub const BTN_NORTH: u16 = 0x133;
+    pub const BTN_WEST: u16 = 0x134;
+    pub const BTN_TL: u16 = 0x136;
+    pub const BTN_TR: u16 = 0x137;
+    pub const BTN_TL2: u16 = 0x138;
+    pub const BTN_TR2: u16 = 0x139;
+    pub const BTN_SELECT: u16 = 0x13a;
+    pub const BTN_START: u16 = 0x13b;
+    pub const BTN_MODE: u16 = 0x13c;
+    pub const BTN_THUMBL: u16 = 0x13d;
+    pub const BTN_THUMBR: u16 = 0x13e;
+    pub const BTN_TRIGGER_HAPPY1: u16 = 0x2c0;
+    pub const BTN_TRIGGER_HAPPY2: u16 = 0x2c1;
+
+    pub const REL_X: u16 = 0x00;
+    pub const REL_Y: u16 = 0x01;
+    pub const REL_HWHEEL: u16 = 0x06;
+    pub const REL_WHEEL: u16 = 0x08;
+    pub const REL_WHEEL_HI_RES: u16 = 0x0b;
+    pub const REL_HWHEEL_HI_RES: u16 = 0x0c;
+
+    pub const ABS_X: u16 = 0x00;
+    pub const ABS_Y: u16 = 0x01;
+    pub const ABS_Z: u16 = 0x02;
+    pub const ABS_RX: u16 = 0x03;
+    pub const ABS_RY: u16 = 0x04;
+    pub const ABS_RZ: u16 = 0x05;
+    pub const ABS_HAT0X: u16 = 0x10;
+    pub const ABS_HAT0Y: u16 = 0x11;
+}
+
+use code::*;
+
+/// An input event.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Event {
+    /// The event type, `EV_KEY`, `EV_ABS`...
+    pub kind: u16,
+
+    /// The key or axis.
+    pub code: u16,
+
+    /// The new value.
+    pub value: i32,
+}
+
+impl Event {
+    /// A key or button being pressed or released.
+    pub fn key(code: u16, pressed: bool) -> Event {
+        Event {
+            kind: EV_KEY,
+            code,
+            value: pressed as i32,
+        }
+    }
+
+    /// Relative motion on an axis.
+    pub fn relative(code: u16, value: i32) -> Event {
+        Event {
+            kind: EV_REL,
+            code,
+            value,
+        }
+    }
+
+    /// A new position on an absolute axis.
+    pub fn absolute(code: u16, value: i32) -> Event {
+        Event {
+            kind: EV_ABS,
+            code,
+            value,
+        }
+    }
+
+    /// The end of a batch of events.
+    pub fn sync() -> Event {
+        Event {
+            kind: EV_SYN,
+            code: SYN_REPORT,
+            value: 0,
+        }
+    }
+}
+
+/// Where events end up.
+pub trait Sink: Send {
+    /// Emit a batch of events, ending with `Event::sync`.
+    fn emit(&mut self, events: &[Event]) -> Result<()>;
+}
+
+impl<T: Sink + ?Sized> Sink for Box<T> {
+    fn emit(&mut self, events: &[Event]) -> Result<()> {
+        (**self).emit(events)
+    }
+}
+
+/// In-memory sink.
+///
+/// Like `transport::Mock` it's a cheap handle to shared state, so a clone can
+/// be kept around to look at the events.
+#[derive(Clone, Default)]
+pub struct Collector {
+    events: Arc<Mutex<Vec<Event>>>,
+}
+
+impl Collector {
+    /// Create an empty collector.
+    pub fn new() -> Collector {
+        Collector::default()
+    }
+
+    fn inner(&self) -> MutexGuard<'_, Vec<Event>> {
+        self.events.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The events emitted so far.
+    pub fn events(&self) -> Vec<Event> {
+        self.inner().clone()
+    }
+
+    /// Forget the events emitted so far.
+    pub fn clear(&self) {
+        self.inner().clear();
+    }
+}
+
+impl Sink for Collector {
+    fn emit(&mut self, events: &[Event]) -> Result<()> {
+        self.inner().extend_from_slice(events);
+
+        Ok(())
+    }
+}
+
+/// An absolute axis of a device.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Absolute {
+    /// The axis.
+    pub code: u16,
+
+    /// The minimum value.
+    pub minimum: i32,
+
+    /// The maximum value.
+    pub maximum: i32,
+
+    /// Changes smaller than this are filtered out as noise.
+    pub fuzz: i32,
+
+    /// Values within this distance from the center are reported as the
+    /// center.
+    pub flat: i32,
+}
+
+#[cfg(target_os = "linux")]
+pub use self::linux::Device;
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::fs::{File, OpenOptions};
+    use std::io::{self, Write};
+    use std::mem;
+    use std::os::unix::io::AsRawFd;
+    use std::slice;
+    use crate::Result;
+    use super::{Absolute, Event, Sink};
+    use super::code::*;
+
+    const fn ioctl(write: bool, nr: u8, size: usize) -> libc::Ioctl {
+        // _IOC(write ? _IOC_WRITE : _IOC_NONE, 'U', nr, size)
+        (((write as usize) << 30) | (size << 16) | ((b'U' as usize) << 8) | nr as usize) as libc::Ioctl
+    }
+
+    const UI_DEV_CREATE: libc::Ioctl = ioctl(false, 1, 0);
+    const UI_DEV_DESTROY: libc::Ioctl = ioctl(false, 2, 0);
+    const UI_DEV_SETUP: libc::Ioctl = ioctl(true, 3, mem::size_of::<libc::uinput_setup>());
+    const UI_ABS_SETUP: libc::Ioctl = ioctl(true, 4, mem::size_of::<libc::uinput_abs_setup>());
+    const UI_SET_EVBIT: libc::Ioctl = ioctl(true, 100, mem::size_of::<libc::c_int>());
+    const UI_SET_KEYBIT: libc::Ioctl = ioctl(true, 101, mem::size_of::<libc::c_int>());
+    const UI_SET_RELBIT: libc::Ioctl = ioctl(true, 102, mem::size_of::<libc::c_int>());
+    const UI_SET_ABSBIT: libc::Ioctl = ioctl(true, 103, mem::size_of::<libc::c_int>());
+
+    /// Bus type reported for the devices, `BUS_VIRTUAL`.
+    const BUS: u16 = 0x06;
+
+    fn check(value: libc::c_int) -> io::Result<()> {
+        if value < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// A virtual device created through `/dev/uinput`.
+    ///
+    /// The device is removed when dropped.
+    pub struct Device {
+        file: File,
+    }
+
+    impl Device {
+        /// Create a device with the given name and capabilities.
+        pub fn new(name: &str, keys: &[u16], relatives: &[u16], absolutes: &[Absolute]) -> Result<Device> {
+            let file = OpenOptions::new()
+                .write(true)
+                .open("/dev/uinput")?;
+
+            let fd = file.as_raw_fd();
+
+            unsafe {
+                if !keys.is_empty() {
+                    check(libc::ioctl(fd, UI_SET_EVBIT, EV_KEY as libc::c_int))?;
+                }
+
+                for &key in keys {
+                    check(libc::ioctl(fd, UI_SET_KEYBIT, key as libc::c_int))?;
+                }
+
+                if !relatives.is_empty() {
+                    check(libc::ioctl(fd, UI_SET_EVBIT, EV_REL as libc::c_int))?;
+                }
+
+                for &relative in relatives {
+                    check(libc::ioctl(fd, UI_SET_RELBIT, relative as libc::c_int))?;
+                }
+
+                if !absolutes.is_empty() {
+                    check(libc::ioctl(fd, UI_SET_EVBIT, EV_ABS as libc::c_int))?;
+                }
+
+                for absolute in absolutes {
+                    check(libc::ioctl(fd, UI_SET_ABSBIT, absolute.code as libc::c_int))?;
+
+                    let mut setup: libc::uinput_abs_setup = mem::zeroed();
+                    setup.code = absolute.code;
+                    setup.absinfo.minimum = absolute.minimum;
+                    setup.absinfo.maximum = absolute.maximum;
+                    setup.absinfo.fuzz = absolute.fuzz;
+                    setup.absinfo.flat = absolute.flat;
+
+                    check(libc::ioctl(fd, UI_ABS_SETUP, &setup))?;
+                }
+
+                let mut setup: libc::uinput_setup = mem::zeroed();
+                setup.id.bustype = BUS;
+                setup.id.vendor = crate::VENDOR_ID;
+                setup.id.product = crate::PRODUCT_ID[0];
+
+                for (to, &from) in setup.name.iter_mut().zip(name.as_bytes().iter().take(libc::UINPUT_MAX_NAME_SIZE - 1)) {
+                    *to = from as libc::c_char;
+                }
+
+                check(libc::ioctl(fd, UI_DEV_SETUP, &setup))?;
+                check(libc::ioctl(fd, UI_DEV_CREATE))?;
+            }
+
+            Ok(Device {
+                file,
+            })
+        }
+    }
+
+    impl Sink for Device {
+        fn emit(&mut self, events: &[Event]) -> Result<()> {
+            let events = events.iter().map(|event| {
+                // The kernel fills in the time.
+                let mut raw: libc::input_event = unsafe { mem::zeroed() };
+                raw.type_ = event.kind;
+                raw.code = event.code;
+                raw.value = event.value;
+
+                raw
+            }).collect::<Vec<_>>();
+
+            let bytes = unsafe {
+                slice::from_raw_parts(events.as_ptr() as *const u8, mem::size_of_val(&events[..]))
+            };
+
+            self.file.write_all(bytes)?;
+
+            Ok(())
+        }
+    }
+
+    impl Drop for Device {
+        fn drop(&mut self) {
+            unsafe {
+                libc::ioctl(self.file.as_raw_fd(), UI_DEV_DESTROY);
+            }
+        }
+    }
+}
+
+/// Buttons of the controller and the gamepad buttons they are reported as.
+const BUTTONS: [(Button, u16); 15] = [
+    (Button::A, BTN_SOUTH),
+    (Button::B, BTN_EAST),
+    (Button::X, BTN_WEST),
+    (Button::Y, BTN_NORTH),
+    (Button::LEFT_BUMPER, BTN_TL),
+    (Button::RIGHT_BUMPER, BTN_TR),
+    (Button::LEFT_TRIGGER, BTN_TL2),
+    (Button::RIGHT_TRIGGER, BTN_TR2),
+    (Button::BACK, BTN_SELECT),
+    (Button::FORWARD, BTN_START),
+    (Button::HOME, BTN_MODE),
+    (Button::STICK, BTN_THUMBL),
+    (Button::TRACK, BTN_THUMBR),
+    (Button::LEFT_GRIP, BTN_TRIGGER_HAPPY1),
+    (Button::RIGHT_GRIP, BTN_TRIGGER_HAPPY2),
+];
+
+/// Axes of the gamepad, in the order they're kept in.
+const AXES: [u16; 8] = [ABS_X, ABS_Y, ABS_RX, ABS_RY, ABS_Z, ABS_RZ, ABS_HAT0X, ABS_HAT0Y];
+
+/// Gamepad reporting the controller state as a standard evdev gamepad.
+///
+/// The stick is reported on `ABS_X`/`ABS_Y`, the right pad on
+/// `ABS_RX`/`ABS_RY`, the triggers on `ABS_Z`/`ABS_RZ` and the left pad
+/// clicks as a hat. Only changes are emitted.
+pub struct Gamepad<S: Sink> {
+    sink: S,
+    buttons: Button,
+    axes: [i32; 8],
+    events: Vec<Event>,
+}
+
+#[cfg(target_os = "linux")]
+impl Gamepad<Device> {
+    /// Create a uinput gamepad device.
+    pub fn create(name: &str) -> Result<Gamepad<Device>> {
+        let stick = |code| Absolute { code, minimum: i16::MIN as i32, maximum: i16::MAX as i32, fuzz: 16, flat: 128 };
+        let trigger = |code| Absolute { code, minimum: 0, maximum: u8::MAX as i32, fuzz: 0, flat: 0 };
+        let hat = |code| Absolute { code, minimum: -1, maximum: 1, fuzz: 0, flat: 0 };
+
+        let keys = BUTTONS.iter().map(|&(_, code)| code).collect::<Vec<_>>();
+
+        let device = Device::new(name, &keys, &[], &[
+            stick(ABS_X), stick(ABS_Y), stick(ABS_RX), stick(ABS_RY),
+            trigger(ABS_Z), trigger(ABS_RZ),
+            hat(ABS_HAT0X), hat(ABS_HAT0Y),
+        ])?;
+
+        Ok(Gamepad::new(device))
+    }
+}
+
+fn flip(value: i16) -> i32 {
+    (-(value as i32)).min(i16::MAX as i32)
+}
+
+fn direction(buttons: Button, negative: Button, positive: Button) -> i32 {
+    buttons.contains(positive) as i32 - buttons.contains(negative) as i32
+}
+
+impl<S: Sink> Gamepad<S> {
+    /// Create a gamepad emitting to the given sink.
+    pub fn new(sink: S) -> Gamepad<S> {
+        Gamepad {
+            sink,
+            buttons: Button::empty(),
+            axes: [0; 8],
+            events: Vec::new(),
+        }
+    }
+
+    /// The sink events are emitted to.
+    pub fn sink(&mut self) -> &mut S {
+        &mut self.sink
+    }
+
+    /// Report the given state, other states than `State::Input` are ignored.
+    pub fn update(&mut self, state: &State) -> Result<()> {
+        let State::Input { buttons, trigger, stick, pad, .. } = *state else {
+            return Ok(());
+        };
+
+        self.apply(buttons, [
+            stick.x as i32,
+            flip(stick.y),
+            pad.right.x as i32,
+            flip(pad.right.y),
+            (trigger.left.clamp(0.0, 1.0) * u8::MAX as f32).round() as i32,
+            (trigger.right.clamp(0.0, 1.0) * u8::MAX as f32).round() as i32,
+            direction(buttons, Button::PAD_LEFT, Button::PAD_RIGHT),
+            direction(buttons, Button::PAD_UP, Button::PAD_DOWN),
+        ])
+    }
+
+    /// Release every button and center every axis.
+    pub fn release(&mut self) -> Result<()> {
+        self.apply(Button::empty(), [0; 8])
+    }
+
+    fn apply(&mut self, buttons: Button, axes: [i32; 8]) -> Result<()> {
+        self.events.clear();
+
+        for &(button, code) in &BUTTONS {
+            if buttons.contains(button) != self.buttons.contains(button) {
+                self.events.push(Event::key(code, buttons.contains(button)));
+            }
+        }
+
+        for (i, &code) in AXES.iter().enumerate() {
+            if axes[i] != self.axes[i] {
+                self.events.push(Event::absolute(code, axes[i]));
+            }
+        }
+
+        self.buttons = buttons;
+        self.axes = axes;
+
+        if self.events.is_empty() {
+            return Ok(());
+        }
+
+        self.events.push(Event::sync());
+        self.sink.emit(&self.events)
+    }
+}
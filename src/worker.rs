@@ -0,0 +1,54 @@
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use crate::{Controller, State, Error, Result};
+
+/// How long the worker waits for input before looking at the commands.
+///
+/// The transports can't be woken up from another thread, so a command waits
+/// for the next report, or for this long while the controller sends nothing,
+/// at the cost of waking the thread 200 times a second.
+const POLL: Duration = Duration::from_millis(5);
+
+/// A command run by the worker with the controller.
+pub(crate) type Command = Box<dyn FnOnce(&mut Controller) + Send>;
+
+/// Move the controller to its own thread, reading the input reports and
+/// running the commands between reads.
+///
+/// Every state or error is handed to `publish`, the thread stops when it
+/// returns `false`, when the controller is disconnected or when every command
+/// sender is dropped.
+pub(crate) fn spawn<F>(mut controller: Controller, mut publish: F) -> mpsc::Sender<Command>
+    where F: FnMut(Result<State>) -> bool + Send + 'static
+{
+    let (sender, commands) = mpsc::channel::<Command>();
+
+    thread::spawn(move || {
+        loop {
+            loop {
+                match commands.try_recv() {
+                    Ok(command) => command(&mut controller),
+                    Err(mpsc::TryRecvError::Empty) => break,
+                    Err(mpsc::TryRecvError::Disconnected) => return,
+                }
+            }
+
+            let (result, last) = match controller.state(POLL) {
+                Ok(state) => (Ok(state), false),
+                Err(Error::Timeout) => continue,
+                Err(e) => {
+                    let last = e.is_disconnected();
+                    (Err(e), last)
+                }
+            };
+
+            if !publish(result) || last {
+                return;
+            }
+        }
+    });
+
+    sender
+}
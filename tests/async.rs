@@ -0,0 +1,68 @@
+#![cfg(feature = "async")]
+
+use std::time::Duration;
+use steamy_base::{AsyncController, Controller, State};
+use steamy_base::transport::Mock;
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(future)
+}
+
+#[test]
+fn commands_and_states() {
+    let mock = Mock::new();
+    let controller = Controller::new(mock.clone(), 0x1102).unwrap();
+    mock.clear();
+
+    mock.input(0x03, &[0x01]);
+
+    block_on(async {
+        let mut controller = AsyncController::new(controller);
+
+        assert_eq!(controller.state().await.unwrap().unwrap(), State::Power(false));
+
+        let details = controller.details().await.unwrap();
+        assert_eq!(&details.serial.controller, b"CONTROLLER");
+
+        controller.feedback(|feedback| feedback.left().amplitude(0x100)).await.unwrap();
+        assert!(mock.sent().iter().any(|packet| packet[..4] == [0x8f, 0x08, 0x01, 0x00]));
+
+        controller.timeout(Duration::from_secs(60)).await.unwrap();
+    });
+}
+
+#[test]
+fn commands_without_consuming() {
+    let mock = Mock::new();
+    let controller = Controller::new(mock.clone(), 0x1102).unwrap();
+
+    for sequence in 0..200u32 {
+        let mut payload = [0u8; 60];
+        payload[..4].copy_from_slice(&sequence.to_le_bytes());
+        mock.input(0x01, &payload);
+    }
+
+    block_on(async {
+        let mut controller = AsyncController::new(controller);
+
+        // Commands keep running though the states aren't consumed, the one
+        // after the last read makes sure its state is queued.
+        while mock.remaining() > 0 {
+            controller.led(50).await.unwrap();
+        }
+
+        controller.led(50).await.unwrap();
+
+        // Only the latest states are kept.
+        let mut sequences = Vec::new();
+
+        for _ in 0..64 {
+            match controller.state().await.unwrap().unwrap() {
+                State::Input { sequence, .. } => sequences.push(sequence),
+                state => panic!("unexpected state {:?}", state),
+            }
+        }
+
+        assert_eq!(sequences, (136..200).collect::<Vec<_>>());
+    });
+}
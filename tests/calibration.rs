@@ -0,0 +1,122 @@
+mod common;
+
+use std::env;
+use std::fs;
+use std::process;
+use steamy_base::{State, Angles, Calibration, Error};
+use steamy_base::calibration::Store;
+use steamy_base::motion::{Vector, GYRO};
+
+fn input(gyro: [i16; 3], acceleration: [i16; 3]) -> State {
+    common::input().gyro(common::angles(gyro)).acceleration(common::angles(acceleration)).state()
+}
+
+const FLAT: [i16; 3] = [0, 0, 16384];
+
+fn gyro(state: State) -> Angles {
+    let State::Input { gyro, .. } = state else {
+        panic!("not an input state");
+    };
+
+    gyro
+}
+
+#[test]
+fn at_rest() {
+    let mut calibration = Calibration::new();
+    calibration.window(10);
+
+    // The first sample only starts the window.
+    for _ in 0..10 {
+        let state = calibration.update(&input([33, -16, 8], FLAT));
+        assert_eq!(gyro(state), Angles { pitch: 33, yaw: -16, roll: 8 });
+    }
+
+    assert!(!calibration.is_calibrated());
+
+    let state = calibration.update(&input([33, -16, 8], FLAT));
+    assert_eq!(gyro(state), Angles::default());
+
+    let bias = calibration.bias().unwrap();
+    assert!((bias - Vector::new(33.0 * GYRO, -16.0 * GYRO, 8.0 * GYRO)).length() < 1e-4);
+
+    // Real motion goes through.
+    let state = calibration.update(&input([1033, -16, 8], [0, 1000, 16384]));
+    assert_eq!(gyro(state), Angles { pitch: 1000, yaw: 0, roll: 0 });
+    assert_eq!(calibration.update(&State::Power(true)), State::Power(true));
+}
+
+#[test]
+fn moving() {
+    let mut calibration = Calibration::new();
+    calibration.window(10);
+
+    // The acceleration keeps changing.
+    for n in 0..100 {
+        calibration.update(&input([33, -16, 8], [0, (n % 2) * 500, 16384]));
+    }
+
+    assert!(!calibration.is_calibrated());
+
+    // The rotation is too fast for a bias.
+    for _ in 0..100 {
+        calibration.update(&input([1000, 0, 0], FLAT));
+    }
+
+    assert!(!calibration.is_calibrated());
+}
+
+#[test]
+fn continuous() {
+    let mut calibration = Calibration::new();
+    calibration.window(5);
+    calibration.continuous(Some(0.5));
+    calibration.set_bias(Some(Vector::default()));
+
+    for _ in 0..6 {
+        calibration.update(&input([100, 0, 0], FLAT));
+    }
+
+    let bias = calibration.bias().unwrap();
+    assert!((bias.x - 50.0 * GYRO).abs() < 1e-4);
+
+    // Without continuous calibration the bias stays.
+    calibration.continuous(None);
+
+    for _ in 0..12 {
+        calibration.update(&input([100, 0, 0], FLAT));
+    }
+
+    assert_eq!(calibration.bias(), Some(bias));
+}
+
+#[test]
+fn store() {
+    let path = env::temp_dir().join(format!("steamy_base-calibration-{}", process::id()));
+    let store = Store::new(&path);
+
+    let serial = *b"FXAB12345\0";
+    assert_eq!(store.file(&serial), path.join("FXAB12345"));
+    assert_eq!(store.file(&[0x01, 0xff, 0, 0, 0, 0, 0, 0, 0, 0]), path.join("01ff0000000000000000"));
+
+    assert_eq!(store.load(&serial).unwrap(), None);
+
+    let bias = Vector::new(0.25, -1.5, 0.125);
+    store.save(&serial, bias).unwrap();
+    assert_eq!(store.load(&serial).unwrap(), Some(bias));
+
+    // Serials without a readable prefix still get a file of their own.
+    let zero = [0u8; 10];
+    assert_eq!(store.file(&zero), path.join("00000000000000000000"));
+    assert_eq!(store.file(&[0, b'A', 0, 0, 0, 0, 0, 0, 0, 0]), path.join("00410000000000000000"));
+
+    store.save(&zero, bias * 2.0).unwrap();
+    assert_eq!(store.load(&zero).unwrap(), Some(bias * 2.0));
+    assert_eq!(store.load(&serial).unwrap(), Some(bias));
+    assert!(!store.file(&zero).with_extension("tmp").exists());
+
+    fs::write(store.file(&serial), "0.25 nope\n").unwrap();
+    assert!(matches!(store.load(&serial), Err(Error::Malformed(_))));
+
+    fs::remove_dir_all(&path).unwrap();
+}
@@ -0,0 +1,97 @@
+//! Fixtures shared by the tests.
+
+#![allow(dead_code)]
+
+use steamy_base::{State, Button, Axis, Trigger, Pad, Angles, Quaternion};
+
+/// Builder for input states, everything at rest unless changed.
+#[derive(Clone, Copy, Debug)]
+pub struct Input {
+    sequence: u32,
+    buttons: Button,
+    trigger: Trigger,
+    pad: Pad,
+    stick: Axis,
+    gyro: Angles,
+    acceleration: Angles,
+    quaternion: Quaternion,
+}
+
+/// Start building an input state.
+pub fn input() -> Input {
+    Input {
+        sequence: 0,
+        buttons: Button::empty(),
+        trigger: Trigger::default(),
+        pad: Pad::default(),
+        stick: Axis::default(),
+        gyro: Angles::default(),
+        acceleration: Angles::default(),
+        quaternion: Quaternion::default(),
+    }
+}
+
+/// Angles from pitch, yaw and roll.
+pub fn angles([pitch, yaw, roll]: [i16; 3]) -> Angles {
+    Angles { pitch, yaw, roll }
+}
+
+impl Input {
+    pub fn sequence(mut self, value: u32) -> Input {
+        self.sequence = value;
+        self
+    }
+
+    pub fn buttons(mut self, value: Button) -> Input {
+        self.buttons = value;
+        self
+    }
+
+    pub fn trigger(mut self, value: Trigger) -> Input {
+        self.trigger = value;
+        self
+    }
+
+    pub fn left(mut self, value: Axis) -> Input {
+        self.pad.left = value;
+        self
+    }
+
+    pub fn right(mut self, value: Axis) -> Input {
+        self.pad.right = value;
+        self
+    }
+
+    pub fn stick(mut self, value: Axis) -> Input {
+        self.stick = value;
+        self
+    }
+
+    pub fn gyro(mut self, value: Angles) -> Input {
+        self.gyro = value;
+        self
+    }
+
+    pub fn acceleration(mut self, value: Angles) -> Input {
+        self.acceleration = value;
+        self
+    }
+
+    pub fn quaternion(mut self, value: Quaternion) -> Input {
+        self.quaternion = value;
+        self
+    }
+
+    pub fn state(self) -> State {
+        State::Input {
+            sequence: self.sequence,
+            buttons: self.buttons,
+            trigger: self.trigger,
+            pad: self.pad,
+            stick: self.stick,
+            gyro: self.gyro,
+            acceleration: self.acceleration,
+            quaternion: self.quaternion,
+        }
+    }
+}
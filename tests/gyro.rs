@@ -0,0 +1,139 @@
+mod common;
+
+use std::time::{Duration, Instant};
+use steamy_base::{State, Button, Angles, Gyro};
+use steamy_base::gyro::{Activation, Output};
+use steamy_base::uinput::{Collector, Event};
+use steamy_base::uinput::code::*;
+
+/// An input state turning at the given rate, 2048 being 125 degrees per
+/// second.
+fn input(buttons: Button, yaw: i16, pitch: i16) -> State {
+    common::input().buttons(buttons).gyro(Angles { pitch, roll: 0, yaw }).state()
+}
+
+fn motion(events: &[Event]) -> (i32, i32) {
+    events.iter().fold((0, 0), |(x, y), event| match (event.kind, event.code) {
+        (EV_REL, REL_X) => (x + event.value, y),
+        (EV_REL, REL_Y) => (x, y + event.value),
+        _ => (x, y),
+    })
+}
+
+#[test]
+fn mouse() {
+    let collector = Collector::new();
+    let mut gyro = Gyro::new(collector.clone(), Output::Mouse);
+    gyro.sensitivity(8.0, 8.0);
+
+    let start = Instant::now();
+    let ms = |n| start + Duration::from_millis(n);
+
+    // Turning right at 125 degrees per second for 100ms is 12.5 degrees.
+    gyro.update(&input(Button::empty(), 0, 0), ms(0)).unwrap();
+    gyro.update(&input(Button::empty(), -2048, 0), ms(100)).unwrap();
+    assert_eq!(motion(&collector.events()), (100, 0));
+
+    // Pitching up moves up.
+    collector.clear();
+    gyro.update(&input(Button::empty(), 0, 1024), ms(200)).unwrap();
+    assert_eq!(motion(&collector.events()), (0, -50));
+}
+
+#[test]
+fn ratcheting() {
+    let collector = Collector::new();
+    let mut gyro = Gyro::new(collector.clone(), Output::Mouse);
+    gyro.activation(Activation::Hold(Button::TRACK_TOUCH));
+
+    let start = Instant::now();
+    let ms = |n| start + Duration::from_millis(n);
+
+    gyro.update(&input(Button::empty(), 2048, 0), ms(0)).unwrap();
+    gyro.update(&input(Button::empty(), 2048, 0), ms(100)).unwrap();
+    assert!(!gyro.is_active());
+    assert!(collector.events().is_empty());
+
+    gyro.update(&input(Button::TRACK_TOUCH, 2048, 0), ms(200)).unwrap();
+    assert!(gyro.is_active());
+    assert_eq!(motion(&collector.events()), (-125, 0));
+
+    gyro.activation(Activation::Toggle(Button::A));
+    collector.clear();
+
+    gyro.update(&input(Button::A, 2048, 0), ms(300)).unwrap();
+    gyro.update(&input(Button::empty(), 2048, 0), ms(400)).unwrap();
+    assert!(gyro.is_active());
+    gyro.update(&input(Button::A, 2048, 0), ms(500)).unwrap();
+    assert!(!gyro.is_active());
+    assert_eq!(motion(&collector.events()), (-250, 0));
+}
+
+#[test]
+fn tightening() {
+    let collector = Collector::new();
+    let mut gyro = Gyro::new(collector.clone(), Output::Mouse);
+    gyro.tightening(Some(125.0));
+
+    let start = Instant::now();
+    let ms = |n| start + Duration::from_millis(n);
+
+    // Half the threshold is scaled down by half.
+    gyro.update(&input(Button::empty(), 0, 0), ms(0)).unwrap();
+    gyro.update(&input(Button::empty(), -1024, 0), ms(100)).unwrap();
+    assert_eq!(motion(&collector.events()), (31, 0));
+
+    collector.clear();
+    gyro.update(&input(Button::empty(), -4096, 0), ms(200)).unwrap();
+    assert_eq!(motion(&collector.events()), (250, 0));
+}
+
+#[test]
+fn smoothing() {
+    let collector = Collector::new();
+    let mut gyro = Gyro::new(collector.clone(), Output::Mouse);
+    gyro.smoothing(Some(20.0));
+
+    let start = Instant::now();
+    let ms = |n| start + Duration::from_millis(n);
+
+    // Slow jitter averages out once the window is full.
+    gyro.update(&input(Button::empty(), 0, 0), ms(0)).unwrap();
+    for n in 1..=9 {
+        let yaw = if n % 2 == 0 { 64 } else { -64 };
+        gyro.update(&input(Button::empty(), yaw, 0), ms(100 * n)).unwrap();
+    }
+
+    collector.clear();
+    gyro.update(&input(Button::empty(), 64, 0), ms(1000)).unwrap();
+    gyro.update(&input(Button::empty(), -64, 0), ms(1100)).unwrap();
+    assert_eq!(motion(&collector.events()), (0, 0));
+
+    // Fast motion goes through.
+    gyro.update(&input(Button::empty(), -2048, 0), ms(1200)).unwrap();
+    assert_eq!(motion(&collector.events()), (125, 0));
+}
+
+#[test]
+fn stick() {
+    let collector = Collector::new();
+    let mut gyro = Gyro::new(collector.clone(), Output::Stick);
+    gyro.sensitivity(0.01, 0.01);
+
+    let start = Instant::now();
+
+    gyro.update(&input(Button::empty(), -4096, -1024), start).unwrap();
+    assert_eq!(collector.events(), [
+        Event::absolute(ABS_RX, 32767),
+        Event::absolute(ABS_RY, 20479),
+        Event::sync(),
+    ]);
+
+    collector.clear();
+    gyro.update(&input(Button::empty(), 0, 0), start).unwrap();
+    assert_eq!(collector.events(), [
+        Event::absolute(ABS_RX, 0),
+        Event::absolute(ABS_RY, 0),
+        Event::sync(),
+    ]);
+}
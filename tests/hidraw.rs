@@ -0,0 +1,103 @@
+#![cfg(target_os = "linux")]
+
+use std::collections::VecDeque;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use steamy_base::{Controller, State, Error};
+use steamy_base::transport::Hidraw;
+use steamy_base::transport::hidraw::Node;
+
+const EIO: i32 = 5;
+
+/// Fake hidraw node behaving like the one of a wired controller.
+#[derive(Clone, Default)]
+struct Fake {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    features: Vec<Vec<u8>>,
+    answer: Option<Vec<u8>>,
+    input: VecDeque<io::Result<Vec<u8>>>,
+}
+
+impl Node for Fake {
+    fn set_feature(&mut self, report: &[u8]) -> io::Result<usize> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.features.push(report.to_vec());
+
+        // Report number first, then the packet.
+        let payload: &[u8] = match (report[1], report[3]) {
+            (0x83, _) => &[0x09, 0x02, 0x00, 0x00, 0x00],
+            (0xae, 0x00) => b"\x00MAINBOARD1",
+            (0xae, 0x01) => b"\x01CONTROLLER",
+            _ => return Ok(report.len()),
+        };
+
+        let mut answer = vec![0u8; 65];
+        answer[1] = report[1];
+        answer[2] = payload.len() as u8;
+        answer[3..3 + payload.len()].copy_from_slice(payload);
+        inner.answer = Some(answer);
+
+        Ok(report.len())
+    }
+
+    fn get_feature(&mut self, report: &mut [u8]) -> io::Result<usize> {
+        let answer = self.inner.lock().unwrap().answer.take().unwrap_or_else(|| vec![0u8; 65]);
+        report.copy_from_slice(&answer);
+
+        Ok(answer.len())
+    }
+
+    fn read_input(&mut self, report: &mut [u8], _timeout: Duration) -> io::Result<usize> {
+        let input = self.inner.lock().unwrap().input.pop_front()
+            .unwrap_or_else(|| Err(io::ErrorKind::TimedOut.into()))?;
+
+        report[..input.len()].copy_from_slice(&input);
+
+        Ok(input.len())
+    }
+}
+
+#[test]
+fn features() {
+    let fake = Fake::default();
+    let mut controller = Controller::new(Hidraw::new(fake.clone()), 0x1102).unwrap();
+
+    {
+        let inner = fake.inner.lock().unwrap();
+        assert_eq!(inner.features.len(), 2);
+        assert!(inner.features.iter().all(|f| f.len() == 65 && f[0] == 0x00));
+        assert_eq!(inner.features[0][1], 0x81);
+        assert_eq!(inner.features[1][1..4], [0x87, 0x15, 0x32]);
+    }
+
+    let details = controller.details().unwrap();
+    assert_eq!(details.build.revision, 2);
+    assert_eq!(&details.serial.controller, b"CONTROLLER");
+}
+
+#[test]
+fn input() {
+    let fake = Fake::default();
+    let mut controller = Controller::new(Hidraw::new(fake.clone()), 0x1102).unwrap();
+
+    let mut report = vec![0u8; 64];
+    report[0] = 0x01;
+    report[2] = 0x03;
+    report[3] = 0x01;
+    report[4] = 0x01;
+
+    {
+        let mut inner = fake.inner.lock().unwrap();
+        inner.input.push_back(Ok(report));
+        inner.input.push_back(Err(io::Error::from_raw_os_error(EIO)));
+    }
+
+    assert_eq!(controller.state(Duration::from_millis(10)).unwrap(), State::Power(false));
+    assert!(matches!(controller.state(Duration::from_millis(10)), Err(Error::Disconnected)));
+    assert!(matches!(controller.state(Duration::from_millis(10)), Err(Error::Timeout)));
+}
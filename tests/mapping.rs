@@ -0,0 +1,114 @@
+#![cfg(feature = "mapping")]
+
+mod common;
+
+use std::fs;
+use std::time::{Duration, SystemTime};
+use steamy_base::{State, Button, Axis, Trigger, Error, Mapper};
+use steamy_base::mapping::{Profile, Watched};
+use steamy_base::uinput::{Collector, Event};
+use steamy_base::uinput::code::*;
+
+const PROFILE: &str = r#"
+[[layers]]
+name = "base"
+bindings = [
+    { input = { button = "A" }, action = { key = 28 } },
+    { input = { pad = { side = "right", direction = "up", click = true } }, action = { mouse_button = "left" } },
+    { input = { trigger = { side = "left", threshold = 0.5 } }, action = { layer = "alt" } },
+    { input = { stick = { direction = "left" } }, action = { gamepad_button = "west" } },
+]
+
+[[layers]]
+name = "alt"
+bindings = [
+    { input = { button = "A" }, action = { macro = [{ press = 29 }, { press = 46 }, { release = 46 }, { release = 29 }] } },
+]
+"#;
+
+fn input(buttons: Button, right: Axis, stick: Axis, left: f32) -> State {
+    common::input().buttons(buttons).right(right).stick(stick).trigger(Trigger { left, right: 0.0 }).state()
+}
+
+fn buttons(value: Button) -> State {
+    input(value, Axis::default(), Axis::default(), 0.0)
+}
+
+#[test]
+fn bindings_and_layers() {
+    let collector = Collector::new();
+    let mut mapper = Mapper::new(collector.clone(), Profile::from_toml(PROFILE).unwrap());
+
+    mapper.update(&buttons(Button::A)).unwrap();
+    mapper.update(&buttons(Button::A)).unwrap();
+    mapper.update(&buttons(Button::empty())).unwrap();
+    assert_eq!(collector.events(), [
+        Event::key(28, true), Event::sync(),
+        Event::key(28, false), Event::sync(),
+    ]);
+
+    // Only the upper part of the clicked right pad.
+    collector.clear();
+    mapper.update(&input(Button::TRACK_TOUCH, Axis { x: 0, y: 20000 }, Axis::default(), 0.0)).unwrap();
+    mapper.update(&input(Button::TRACK_TOUCH | Button::TRACK, Axis { x: 0, y: -20000 }, Axis::default(), 0.0)).unwrap();
+    mapper.update(&input(Button::TRACK_TOUCH | Button::TRACK, Axis { x: 0, y: 20000 }, Axis::default(), 0.0)).unwrap();
+    assert_eq!(collector.events(), [Event::key(BTN_LEFT, true), Event::sync()]);
+
+    collector.clear();
+    mapper.update(&input(Button::empty(), Axis::default(), Axis { x: -30000, y: 0 }, 0.0)).unwrap();
+    assert_eq!(collector.events(), [Event::key(BTN_LEFT, false), Event::key(BTN_WEST, true), Event::sync()]);
+
+    // Holding the trigger switches layer, A runs the macro then.
+    collector.clear();
+    mapper.update(&input(Button::empty(), Axis::default(), Axis::default(), 0.8)).unwrap();
+    assert_eq!(mapper.layers(), ["base", "alt"]);
+
+    mapper.update(&input(Button::A, Axis::default(), Axis::default(), 0.8)).unwrap();
+    mapper.update(&input(Button::empty(), Axis::default(), Axis::default(), 0.0)).unwrap();
+    assert_eq!(mapper.layers(), ["base"]);
+    assert_eq!(collector.events(), [
+        Event::key(BTN_WEST, false), Event::sync(),
+        Event::key(29, true), Event::key(46, true), Event::key(46, false), Event::key(29, false), Event::sync(),
+    ]);
+}
+
+#[test]
+fn invalid_profiles() {
+    assert!(matches!(Profile::from_json(r#"{ "layers": [{ "name": "base", "bindings": [
+        { "input": { "button": "NOPE" }, "action": { "key": 1 } }
+    ] }] }"#), Err(Error::Profile(_))));
+
+    assert!(matches!(Profile::from_json(r#"{ "layers": [{ "name": "base", "bindings": [
+        { "input": { "button": "B" }, "action": { "toggle_layer": "missing" } }
+    ] }] }"#), Err(Error::Profile(_))));
+
+    let profile = Profile::from_json(r#"{ "layers": [{ "name": "base", "bindings": [
+        { "input": { "button": "LEFT_GRIP" }, "action": { "key": 1 } }
+    ] }] }"#).unwrap();
+    assert_eq!(Profile::from_toml(&toml::to_string(&profile).unwrap()).unwrap(), profile);
+}
+
+#[test]
+fn hot_reload() {
+    let path = std::env::temp_dir().join(format!("steamy_base-{}.toml", std::process::id()));
+    fs::write(&path, PROFILE).unwrap();
+
+    let (mut watched, profile) = Watched::new(&path).unwrap();
+    assert_eq!(profile.layers.len(), 2);
+    assert!(watched.check().unwrap().is_none());
+
+    fs::write(&path, "[[layers]]\nname = \"only\"\n").unwrap();
+    let later = SystemTime::now() + Duration::from_secs(5);
+    fs::File::options().write(true).open(&path).unwrap().set_modified(later).unwrap();
+
+    let reloaded = watched.check().unwrap().unwrap();
+    assert_eq!(reloaded.layers[0].name, "only");
+
+    let collector = Collector::new();
+    let mut mapper = Mapper::new(collector.clone(), profile);
+    mapper.update(&buttons(Button::A)).unwrap();
+    mapper.set_profile(reloaded).unwrap();
+    assert_eq!(collector.events()[2..], [Event::key(28, false), Event::sync()]);
+
+    fs::remove_file(&path).unwrap();
+}
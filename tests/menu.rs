@@ -0,0 +1,81 @@
+mod common;
+
+use steamy_base::{State, Button, Axis, Side, Menu, Pulse};
+use steamy_base::menu::{Layout, Commit};
+
+fn input(buttons: Button, left: Axis) -> State {
+    common::input().buttons(buttons).left(left).state()
+}
+
+#[test]
+fn radial_cells() {
+    let layout = Layout::Radial { sectors: 4, center: 0.25 };
+
+    assert_eq!(layout.cell(Axis { x: 0, y: 0 }), None);
+    assert_eq!(layout.cell(Axis { x: 0, y: 30000 }), Some(0));
+    assert_eq!(layout.cell(Axis { x: 30000, y: 0 }), Some(1));
+    assert_eq!(layout.cell(Axis { x: 0, y: -30000 }), Some(2));
+    assert_eq!(layout.cell(Axis { x: -30000, y: 0 }), Some(3));
+
+    // The first sector is centered on the top.
+    assert_eq!(layout.cell(Axis { x: -10000, y: 30000 }), Some(0));
+}
+
+#[test]
+fn grid_cells() {
+    let layout = Layout::Grid { columns: 3, rows: 2 };
+
+    assert_eq!(layout.len(), 6);
+    assert_eq!(layout.cell(Axis { x: i16::MIN, y: i16::MAX }), Some(0));
+    assert_eq!(layout.cell(Axis { x: 0, y: 10000 }), Some(1));
+    assert_eq!(layout.cell(Axis { x: i16::MAX, y: i16::MIN }), Some(5));
+    assert_eq!(Layout::Grid { columns: 0, rows: 2 }.cell(Axis::default()), None);
+}
+
+#[test]
+fn hover_and_click() {
+    let pulse = Pulse { left: true, amplitude: 100, period: 0, count: 1 };
+    let mut menu = Menu::new(Side::Left, Layout::Radial { sectors: 8, center: 0.3 });
+    menu.pulse(Some(pulse));
+
+    let update = menu.update(&input(Button::PAD_TOUCH, Axis { x: 0, y: 30000 }));
+    assert_eq!((update.hovered, update.selected, update.pulse), (Some(0), None, Some(pulse)));
+
+    // Staying in the same sector doesn't pulse.
+    let update = menu.update(&input(Button::PAD_TOUCH, Axis { x: 1000, y: 30000 }));
+    assert_eq!((update.hovered, update.pulse), (Some(0), None));
+
+    let update = menu.update(&input(Button::PAD_TOUCH, Axis { x: 30000, y: 0 }));
+    assert_eq!((update.hovered, update.pulse), (Some(2), Some(pulse)));
+
+    let update = menu.update(&input(Button::PAD_TOUCH | Button::PAD, Axis { x: 30000, y: 0 }));
+    assert_eq!(update.selected, Some(2));
+
+    // Holding the click doesn't select again.
+    let update = menu.update(&input(Button::PAD_TOUCH | Button::PAD, Axis { x: 30000, y: 0 }));
+    assert_eq!(update.selected, None);
+
+    // The right pad is ignored.
+    let update = menu.update(&input(Button::TRACK_TOUCH, Axis { x: 30000, y: 0 }));
+    assert_eq!(update.hovered, None);
+    assert_eq!(menu.hovered(), None);
+}
+
+#[test]
+fn select_on_release() {
+    let mut menu = Menu::new(Side::Left, Layout::Grid { columns: 2, rows: 2 });
+    menu.commit(Commit::Release);
+
+    menu.update(&input(Button::PAD_TOUCH, Axis { x: 10000, y: 10000 }));
+    let update = menu.update(&input(Button::PAD_TOUCH | Button::PAD, Axis { x: 10000, y: -10000 }));
+    assert_eq!((update.hovered, update.selected, update.pulse), (Some(3), None, None));
+
+    let update = menu.update(&input(Button::empty(), Axis::default()));
+    assert_eq!((update.hovered, update.selected), (None, Some(3)));
+
+    // Lifting from the center of a radial menu cancels.
+    menu.set_layout(Layout::Radial { sectors: 4, center: 0.5 });
+    menu.update(&input(Button::PAD_TOUCH, Axis { x: 1000, y: 1000 }));
+    let update = menu.update(&input(Button::empty(), Axis::default()));
+    assert_eq!(update.selected, None);
+}
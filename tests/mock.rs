@@ -0,0 +1,200 @@
+use std::time::Duration;
+use steamy_base::{Controller, State, Button, Axis, Battery, Error};
+use steamy_base::transport::Mock;
+
+const WIRED: u16 = 0x1102;
+const WIRELESS: u16 = 0x1142;
+
+fn input(sequence: u32, buttons: u32, left: Axis, right: Axis) -> [u8; 60] {
+    let mut payload = [0u8; 60];
+    payload[0..4].copy_from_slice(&sequence.to_le_bytes());
+    payload[4..8].copy_from_slice(&(buttons << 8).to_be_bytes());
+    payload[12..14].copy_from_slice(&left.x.to_le_bytes());
+    payload[14..16].copy_from_slice(&left.y.to_le_bytes());
+    payload[16..18].copy_from_slice(&right.x.to_le_bytes());
+    payload[18..20].copy_from_slice(&right.y.to_le_bytes());
+
+    payload
+}
+
+#[test]
+fn details() {
+    let mock = Mock::new();
+    let mut controller = Controller::new(mock.clone(), WIRELESS).unwrap();
+    let details = controller.details().unwrap();
+
+    assert_eq!(details.build.revision, 1);
+    assert_eq!(&details.serial.mainboard, b"MAINBOARD1");
+    assert_eq!(&details.serial.controller, b"CONTROLLER");
+    assert_eq!(&details.receiver.unwrap().serial, b"RECEIVER01");
+}
+
+#[test]
+fn connection() {
+    let mock = Mock::new();
+    let mut controller = Controller::new(mock.clone(), WIRELESS).unwrap();
+    assert!(controller.is_connected());
+
+    mock.connected(false);
+    assert!(!controller.is_connected());
+}
+
+#[test]
+fn input_state() {
+    let mock = Mock::new();
+    let mut controller = Controller::new(mock.clone(), WIRED).unwrap();
+
+    let right = Axis { x: 1200, y: -300 };
+    mock.input(0x01, &input(7, (Button::A | Button::TRACK_TOUCH).bits(), Axis::default(), right));
+
+    match controller.state(Duration::from_millis(10)).unwrap() {
+        State::Input { sequence, buttons, pad, .. } => {
+            assert_eq!(sequence, 7);
+            assert_eq!(buttons, Button::A | Button::TRACK_TOUCH);
+            assert_eq!(pad.right, right);
+        }
+
+        state => panic!("unexpected state {:?}", state),
+    }
+
+    assert!(matches!(controller.state(Duration::from_millis(10)), Err(Error::Timeout)));
+}
+
+#[test]
+fn unknown_report() {
+    let mock = Mock::new();
+    let mut controller = Controller::new(mock.clone(), WIRED).unwrap();
+
+    mock.input(0x42, &[0x01, 0x02, 0x03]);
+
+    match controller.state(Duration::from_millis(10)).unwrap() {
+        State::Unknown { id, payload } => {
+            assert_eq!(id, 0x42);
+            assert_eq!(&payload[..4], &[0x01, 0x02, 0x03, 0x00]);
+        }
+
+        state => panic!("unexpected state {:?}", state),
+    }
+
+    controller.strict(true);
+    mock.input(0x42, &[0x00; 8]);
+    assert!(matches!(controller.state(Duration::from_millis(10)), Err(Error::UnknownReport(0x42))));
+}
+
+#[test]
+fn unknown_buttons() {
+    let mock = Mock::new();
+    let mut controller = Controller::new(mock.clone(), WIRED).unwrap();
+
+    let bits = Button::A.bits() | 0x20;
+    mock.input(0x01, &input(1, bits, Axis::default(), Axis::default()));
+
+    match controller.state(Duration::from_millis(10)).unwrap() {
+        State::Input { buttons, .. } => {
+            assert!(buttons.contains(Button::A));
+            assert_eq!(buttons.unknown(), 0x20);
+        }
+
+        state => panic!("unexpected state {:?}", state),
+    }
+
+    controller.strict(true);
+    mock.input(0x01, &input(2, bits, Axis::default(), Axis::default()));
+    assert!(matches!(controller.state(Duration::from_millis(10)), Err(Error::UnknownButtons(b)) if b == bits));
+}
+
+#[test]
+fn power_resets() {
+    let mock = Mock::new();
+    let mut controller = Controller::new(mock.clone(), WIRELESS).unwrap();
+    controller.lizard().enable().unwrap();
+    mock.clear();
+
+    mock.input(0x03, &[0x02]);
+    let (state, raw) = controller.state_with_raw(Duration::from_millis(10)).unwrap();
+    assert_eq!(state, State::Power(true));
+    assert_eq!(&raw[..5], &[0x01, 0x00, 0x03, 0x01, 0x02]);
+
+    let sent = mock.sent();
+    assert_eq!(sent[0][0], 0x85);
+    assert_eq!(sent[1][0], 0x87);
+}
+
+#[test]
+fn feedback() {
+    let mock = Mock::new();
+    let mut controller = Controller::new(mock.clone(), WIRED).unwrap();
+    mock.clear();
+
+    controller.feedback().left().amplitude(300).period(10).count(2).send().unwrap();
+
+    let sent = mock.sent();
+    assert_eq!(&sent[0][..10], &[0x8f, 0x08, 0x01, 0x2c, 0x01, 0x0a, 0x00, 0x02, 0x00, 0x00]);
+}
+
+#[test]
+fn led_level() {
+    let mock = Mock::new();
+    let mut controller = Controller::new(mock.clone(), WIRED).unwrap();
+    mock.clear();
+
+    controller.led().level(42).unwrap();
+
+    let sent = mock.sent();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(&sent[0][..4], &[0x87, 0x03, 0x2d, 42]);
+    assert_eq!(controller.settings().led, Some(42));
+}
+
+#[test]
+fn stick_and_pad() {
+    let mock = Mock::new();
+    let mut controller = Controller::new(mock.clone(), WIRED).unwrap();
+
+    let stick = Axis { x: 100, y: 200 };
+    let left = Axis { x: -5000, y: 3000 };
+    let both = Button::PAD_AND_STICK.bits();
+
+    mock.input(0x01, &input(1, 0, stick, Axis::default()));
+    mock.input(0x01, &input(2, both | Button::PAD_TOUCH.bits(), left, Axis::default()));
+    mock.input(0x01, &input(3, both, Axis { x: 150, y: 250 }, Axis::default()));
+    mock.input(0x01, &input(4, Button::PAD_TOUCH.bits(), left, Axis::default()));
+
+    let mut read = || match controller.state(Duration::from_millis(10)).unwrap() {
+        State::Input { buttons, pad, stick, .. } => (buttons.contains(Button::PAD_TOUCH), pad.left, stick),
+        state => panic!("unexpected state {:?}", state),
+    };
+
+    assert_eq!(read(), (false, Axis::default(), stick));
+    assert_eq!(read(), (true, left, stick));
+    assert_eq!(read(), (true, left, Axis { x: 150, y: 250 }));
+    assert_eq!(read(), (true, left, Axis::default()));
+}
+
+#[test]
+fn battery_status() {
+    let controller = Controller::new(Mock::new(), WIRED).unwrap();
+    assert!(matches!(controller.battery(), Err(Error::Unsupported(_))));
+
+    let mock = Mock::new();
+    let mut controller = Controller::new(mock.clone(), WIRELESS).unwrap();
+    assert_eq!(controller.battery().unwrap(), None);
+
+    mock.input(0x04, &[0x2a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x7c, 0x0b, 0x55]);
+
+    let battery = Battery { voltage: 2940, percent: 85 };
+    assert_eq!(controller.state(Duration::from_millis(10)).unwrap(), State::Status { sequence: 42, battery });
+    assert_eq!(controller.battery().unwrap(), Some(battery));
+}
+
+#[test]
+fn disconnected_errors() {
+    assert!(Error::Disconnected.is_disconnected());
+    assert!(Error::Usb(rusb::Error::NoDevice).is_disconnected());
+    assert!(Error::Usb(rusb::Error::Io).is_disconnected());
+
+    // Stalls and unknown errors may be transient.
+    assert!(!Error::Usb(rusb::Error::Pipe).is_disconnected());
+    assert!(!Error::Usb(rusb::Error::Other).is_disconnected());
+    assert!(!Error::Timeout.is_disconnected());
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use std::time::Duration;
+use steamy_base::{State, Quaternion, Fusion};
+use steamy_base::motion::{Filter, Rotation, Vector};
+
+/// An input state with the raw gyro and accelerometer values, 16384 being
+/// 1000 degrees per second and a standard gravity.
+fn input(sequence: u32, gyro: [i16; 3], acceleration: [i16; 3], quaternion: Quaternion) -> State {
+    common::input()
+        .sequence(sequence)
+        .gyro(common::angles(gyro))
+        .acceleration(common::angles(acceleration))
+        .quaternion(quaternion)
+        .state()
+}
+
+const FLAT: [i16; 3] = [0, 0, 16384];
+const STILL: [i16; 3] = [0, 0, 0];
+
+fn close(a: Vector, b: Vector, tolerance: f32) -> bool {
+    (a - b).length() < tolerance
+}
+
+#[test]
+fn units() {
+    let mut fusion = Fusion::default();
+    let motion = fusion.update(&input(0, [16384, -8192, 0], FLAT, Quaternion::default())).unwrap();
+
+    assert_eq!(motion.gyro, Vector::new(1000.0, -500.0, 0.0));
+    assert_eq!(motion.acceleration, Vector::new(0.0, 0.0, 1.0));
+    assert_eq!(fusion.update(&State::Power(true)), None);
+}
+
+#[test]
+fn firmware() {
+    let mut fusion = Fusion::new(Filter::Firmware);
+
+    let motion = fusion.update(&input(0, STILL, FLAT, Quaternion { w: i16::MAX, x: 0, y: 0, z: 0 })).unwrap();
+    assert_eq!(motion.orientation, Rotation::IDENTITY);
+    assert!(close(motion.gravity, Vector::new(0.0, 0.0, -1.0), 1e-6));
+
+    // Half a turn around X turns gravity upside down.
+    let motion = fusion.update(&input(1, STILL, FLAT, Quaternion { w: 0, x: 16384, y: 0, z: 0 })).unwrap();
+    assert!(close(motion.gravity, Vector::new(0.0, 0.0, 1.0), 1e-6));
+}
+
+#[test]
+fn timestamps() {
+    let mut fusion = Fusion::default();
+    fusion.period(Duration::from_millis(5));
+
+    assert_eq!(fusion.update(&input(u32::MAX - 1, STILL, FLAT, Quaternion::default())).unwrap().time, Duration::ZERO);
+    assert_eq!(fusion.update(&input(u32::MAX, STILL, FLAT, Quaternion::default())).unwrap().time, Duration::from_millis(5));
+
+    // Missed reports and wrapping sequences are accounted for.
+    assert_eq!(fusion.update(&input(3, STILL, FLAT, Quaternion::default())).unwrap().time, Duration::from_millis(25));
+}
+
+#[test]
+fn complementary_gyro() {
+    let mut fusion = Fusion::new(Filter::Complementary(1.0));
+
+    // Turning around the vertical at 90 degrees per second for a second.
+    let rate = (90.0 / 1000.0 * 16384.0) as i16;
+    let mut motion = None;
+
+    for sequence in 0..=250 {
+        motion = fusion.update(&input(sequence, [0, 0, rate], FLAT, Quaternion::default()));
+    }
+
+    let motion = motion.unwrap();
+    assert!((motion.orientation.angle() - 90.0).abs() < 0.5);
+    assert!(close(motion.orientation.rotate(Vector::new(1.0, 0.0, 0.0)), Vector::new(0.0, 1.0, 0.0), 0.01));
+    assert!(close(motion.gravity, Vector::new(0.0, 0.0, -1.0), 1e-3));
+}
+
+#[test]
+fn tilt() {
+    for filter in [Filter::Complementary(2.0), Filter::Madgwick(1.0)] {
+        let mut fusion = Fusion::new(filter);
+
+        // Starts from the accelerometer.
+        let motion = fusion.update(&input(0, STILL, [0, 16384, 0], Quaternion::default())).unwrap();
+        assert!(close(motion.gravity, Vector::new(0.0, -1.0, 0.0), 1e-3), "{:?}", filter);
+
+        // Then slowly follows it.
+        let mut motion = None;
+
+        for sequence in 1..=1500 {
+            motion = fusion.update(&input(sequence, STILL, FLAT, Quaternion::default()));
+        }
+
+        let motion = motion.unwrap();
+        assert!(close(motion.gravity, Vector::new(0.0, 0.0, -1.0), 0.02), "{:?} {:?}", filter, motion.gravity);
+    }
+}
+
+#[test]
+fn drift() {
+    let mut fusion = Fusion::new(Filter::Complementary(1.0));
+    fusion.drift(Some(1.0));
+
+    // A gyro drifting by 2 degrees per second around the vertical.
+    let rate = (2.0 / 1000.0 * 16384.0) as i16;
+    let mut motion = None;
+
+    for sequence in 0..=2500 {
+        motion = fusion.update(&input(sequence, [0, 0, rate], FLAT, Quaternion::default()));
+    }
+
+    let drift = rate as f32 * 1000.0 / 16384.0;
+    assert!((fusion.yaw_drift() - drift).abs() < 0.01);
+    assert!(motion.unwrap().gyro.length() < 0.01);
+
+    fusion.reset();
+    assert_eq!(fusion.yaw_drift(), 0.0);
+}
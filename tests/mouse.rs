@@ -0,0 +1,101 @@
+mod common;
+
+use std::time::{Duration, Instant};
+use steamy_base::{State, Button, Axis, Mouse, Pulse};
+use steamy_base::mouse::Acceleration;
+use steamy_base::uinput::{Collector, Event};
+use steamy_base::uinput::code::*;
+
+fn input(buttons: Button, right: Axis) -> State {
+    common::input().buttons(buttons).right(right).state()
+}
+
+fn motion(events: &[Event]) -> (i32, i32) {
+    events.iter().fold((0, 0), |(x, y), event| match (event.kind, event.code) {
+        (EV_REL, REL_X) => (x + event.value, y),
+        (EV_REL, REL_Y) => (x, y + event.value),
+        _ => (x, y),
+    })
+}
+
+#[test]
+fn motion_and_click() {
+    let collector = Collector::new();
+    let mut mouse = Mouse::new(collector.clone());
+    mouse.sensitivity(0.01);
+    mouse.inertia(None);
+
+    let start = Instant::now();
+    let ms = |n| start + Duration::from_millis(n);
+
+    mouse.update(&input(Button::TRACK_TOUCH, Axis { x: 0, y: 0 }), ms(0)).unwrap();
+    mouse.update(&input(Button::TRACK_TOUCH, Axis { x: 1000, y: 500 }), ms(4)).unwrap();
+    assert_eq!(motion(&collector.events()), (10, -5));
+
+    collector.clear();
+    mouse.update(&input(Button::TRACK_TOUCH | Button::TRACK, Axis { x: 1000, y: 500 }), ms(8)).unwrap();
+    assert_eq!(collector.events(), [Event::key(BTN_LEFT, true), Event::sync()]);
+
+    // Lifting the finger without inertia stops the pointer right away.
+    collector.clear();
+    mouse.update(&input(Button::empty(), Axis::default()), ms(12)).unwrap();
+    mouse.coast(ms(50)).unwrap();
+    assert_eq!(collector.events(), [Event::key(BTN_LEFT, false), Event::sync()]);
+}
+
+#[test]
+fn acceleration() {
+    let collector = Collector::new();
+    let mut mouse = Mouse::new(collector.clone());
+    mouse.sensitivity(0.01);
+    mouse.inertia(None);
+    mouse.acceleration(Acceleration::Linear { factor: 1.0, limit: 3.0 });
+
+    let start = Instant::now();
+    mouse.update(&input(Button::TRACK_TOUCH, Axis { x: 0, y: 0 }), start).unwrap();
+    mouse.update(&input(Button::TRACK_TOUCH, Axis { x: 400, y: 0 }), start + Duration::from_millis(4)).unwrap();
+
+    // 4 pixels in 4 milliseconds doubles the motion.
+    assert_eq!(motion(&collector.events()), (8, 0));
+}
+
+#[test]
+fn inertia_and_ticks() {
+    let collector = Collector::new();
+    let mut mouse = Mouse::new(collector.clone());
+    mouse.sensitivity(0.01);
+    mouse.inertia(Some(4.0));
+
+    let pulse = Pulse { left: false, amplitude: 100, period: 0, count: 1 };
+    mouse.ticks(Some((10.0, pulse)));
+
+    let start = Instant::now();
+    let ms = |n| start + Duration::from_millis(n);
+
+    assert_eq!(mouse.update(&input(Button::TRACK_TOUCH, Axis { x: 0, y: 0 }), ms(0)).unwrap(), None);
+    assert_eq!(mouse.update(&input(Button::TRACK_TOUCH, Axis { x: 900, y: 0 }), ms(10)).unwrap(), None);
+    assert_eq!(mouse.update(&input(Button::TRACK_TOUCH, Axis { x: 3500, y: 0 }), ms(20)).unwrap(), Some(Pulse { count: 3, .. pulse }));
+
+    collector.clear();
+    mouse.update(&input(Button::empty(), Axis::default()), ms(30)).unwrap();
+    assert!(mouse.is_gliding());
+
+    let mut previous = i32::MAX;
+
+    for step in 1..=10 {
+        collector.clear();
+        mouse.coast(ms(30 + step * 10)).unwrap();
+
+        let (x, y) = motion(&collector.events());
+        assert!(x > 0 && x <= previous && y == 0);
+        previous = x;
+    }
+
+    mouse.coast(ms(5000)).unwrap();
+    assert!(!mouse.is_gliding());
+
+    // Touching again stops the glide.
+    mouse.update(&input(Button::empty(), Axis::default()), ms(5010)).unwrap();
+    mouse.update(&input(Button::TRACK_TOUCH, Axis { x: 0, y: 0 }), ms(5020)).unwrap();
+    assert!(!mouse.is_gliding());
+}
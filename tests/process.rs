@@ -0,0 +1,120 @@
+mod common;
+
+use steamy_base::{State, Axis, Pad, Processor};
+use steamy_base::process::{Deadzone, Curve, Processing};
+
+const MAX: f32 = i16::MAX as f32;
+
+fn axis(x: f32, y: f32) -> Axis {
+    Axis { x: (x * MAX) as i16, y: (y * MAX) as i16 }
+}
+
+fn close((x, y): (f32, f32), (ex, ey): (f32, f32)) -> bool {
+    (x - ex).abs() < 1e-3 && (y - ey).abs() < 1e-3
+}
+
+#[test]
+fn untouched() {
+    let processor = Processor::new();
+
+    assert!(close(processor.process(axis(0.5, -0.25)), (0.5, -0.25)));
+    assert_eq!(processor.axis(Axis { x: 1234, y: -4321 }), Axis { x: 1234, y: -4321 });
+    assert!(close(processor.process(Axis { x: i16::MIN, y: 0 }), (-1.0, 0.0)));
+}
+
+#[test]
+fn radial() {
+    let mut processor = Processor::new();
+    processor.deadzone(Deadzone::Radial(0.2));
+
+    assert_eq!(processor.process(axis(0.1, 0.1)), (0.0, 0.0));
+
+    // The remaining range is stretched and the direction kept.
+    assert!(close(processor.process(axis(0.6, 0.0)), (0.5, 0.0)));
+    assert!(close(processor.process(axis(0.36, 0.48)), (0.3, 0.4)));
+}
+
+#[test]
+fn axial() {
+    let mut processor = Processor::new();
+    processor.deadzone(Deadzone::Axial(0.2));
+
+    assert!(close(processor.process(axis(0.6, 0.1)), (0.5, 0.0)));
+    assert!(close(processor.process(axis(-0.1, -0.6)), (0.0, -0.5)));
+}
+
+#[test]
+fn cross() {
+    let mut processor = Processor::new();
+    processor.deadzone(Deadzone::Cross(0.2));
+
+    // Close to an axis snaps to it.
+    assert!(close(processor.process(axis(0.6, 0.15)), (0.5, 0.0)));
+    assert_eq!(processor.process(axis(0.15, 0.15)), (0.0, 0.0));
+
+    let (x, y) = processor.process(axis(0.5, 0.5));
+    assert!(x > 0.0 && (x - y).abs() < 1e-3);
+}
+
+#[test]
+fn outer() {
+    let mut processor = Processor::new();
+    processor.outer(0.8);
+
+    assert!(close(processor.process(axis(0.4, 0.0)), (0.5, 0.0)));
+    assert!(close(processor.process(axis(0.9, 0.0)), (1.0, 0.0)));
+
+    // The corners are clamped to the circle.
+    let (x, y) = processor.process(axis(1.0, 1.0));
+    assert!((x.hypot(y) - 1.0).abs() < 1e-3);
+}
+
+#[test]
+fn curves() {
+    assert_eq!(Curve::Linear.apply(0.3), 0.3);
+    assert!((Curve::Power(2.0).apply(0.5) - 0.25).abs() < 1e-6);
+
+    let table = Curve::Table(vec![0.0, 0.1, 1.0]);
+    assert!((table.apply(0.25) - 0.05).abs() < 1e-6);
+    assert!((table.apply(0.75) - 0.55).abs() < 1e-6);
+    assert_eq!(table.apply(1.0), 1.0);
+    assert_eq!(table.apply(2.0), 1.0);
+
+    let mut processor = Processor::new();
+    processor.curve(Curve::Power(2.0));
+    assert!(close(processor.process(axis(0.0, -0.5)), (0.0, -0.25)));
+}
+
+#[test]
+fn anti_deadzone() {
+    let mut processor = Processor::new();
+    processor.deadzone(Deadzone::Radial(0.1));
+    processor.anti_deadzone(0.25);
+
+    assert_eq!(processor.process(axis(0.05, 0.0)), (0.0, 0.0));
+    assert!(close(processor.process(axis(0.1001, 0.0)), (0.25, 0.0)));
+    assert!(close(processor.process(axis(1.0, 0.0)), (1.0, 0.0)));
+}
+
+#[test]
+fn state() {
+    let mut stick = Processor::new();
+    stick.deadzone(Deadzone::Radial(0.5));
+
+    let processing = Processing {
+        stick: stick.clone(),
+        left: Some(stick),
+        right: None,
+    };
+
+    let small = axis(0.25, 0.0);
+    let state = common::input().sequence(1).left(small).right(small).stick(small).state();
+
+    let State::Input { stick, pad, .. } = processing.apply(&state) else {
+        panic!("not an input state");
+    };
+
+    assert_eq!(stick, Axis::default());
+    assert_eq!(pad, Pad { left: Axis::default(), right: small });
+    assert_eq!(processing.apply(&State::Power(true)), State::Power(true));
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use steamy_base::{State, Button, Trigger, Side, Pull, Pulse};
+use steamy_base::process::Level;
+
+fn input(buttons: Button, right: f32) -> State {
+    common::input().buttons(buttons).trigger(Trigger { left: 0.0, right }).state()
+}
+
+fn levels(pull: &mut Pull, values: &[f32]) -> Vec<Level> {
+    values.iter().map(|&value| pull.update(&input(Button::empty(), value)).level).collect()
+}
+
+#[test]
+fn soft_and_full() {
+    let pulse = Pulse { left: true, amplitude: 300, period: 0, count: 1 };
+    let mut pull = Pull::new(Side::Right);
+    pull.activation(0.4);
+    pull.pulse(Some(pulse));
+
+    let press = pull.update(&input(Button::empty(), 0.3));
+    assert_eq!((press.level, press.pulse), (Level::Released, None));
+
+    // The pulse is sent on the side of the trigger.
+    let press = pull.update(&input(Button::empty(), 0.5));
+    assert!(press.is_changed());
+    assert_eq!((press.level, press.pulse), (Level::Soft, Some(Pulse { left: false, .. pulse })));
+
+    let press = pull.update(&input(Button::RIGHT_TRIGGER, 0.9));
+    assert_eq!((press.previous, press.level), (Level::Soft, Level::Full));
+    assert!(press.pulse.is_some());
+
+    let press = pull.update(&input(Button::empty(), 0.5));
+    assert_eq!((press.level, press.pulse), (Level::Soft, None));
+    assert_eq!(pull.level(), Level::Soft);
+
+    assert_eq!(levels(&mut pull, &[0.2, 1.0]), [Level::Released, Level::Full]);
+}
+
+#[test]
+fn deadzones() {
+    let mut pull = Pull::new(Side::Right);
+    pull.deadzone(0.2);
+    pull.outer(0.6);
+    pull.activation(0.5);
+
+    assert_eq!(pull.update(&input(Button::empty(), 0.1)).value, 0.0);
+    assert!((pull.update(&input(Button::empty(), 0.3)).value - 0.25).abs() < 1e-6);
+    assert_eq!(pull.level(), Level::Released);
+
+    let press = pull.update(&input(Button::empty(), 0.7));
+    assert_eq!((press.value, press.level), (1.0, Level::Full));
+
+    // The left trigger is ignored.
+    let mut pull = Pull::new(Side::Left);
+    assert_eq!(levels(&mut pull, &[1.0]), [Level::Released]);
+}
+
+#[test]
+fn hair_trigger() {
+    let mut pull = Pull::new(Side::Right);
+    pull.activation(0.5);
+    pull.hair(Some(0.1));
+
+    // The first press needs the activation point, then releasing a little
+    // from the deepest point ends the pull and pressing a little starts it
+    // again.
+    assert_eq!(
+        levels(&mut pull, &[0.3, 0.55, 0.7, 0.65, 0.55, 0.5, 0.45, 0.6, 0.65]),
+        [Level::Released, Level::Soft, Level::Soft, Level::Soft, Level::Released, Level::Released, Level::Released, Level::Soft, Level::Soft],
+    );
+
+    // Fully released, the activation point is needed again.
+    assert_eq!(
+        levels(&mut pull, &[0.0, 0.2, 0.35, 0.5]),
+        [Level::Released, Level::Released, Level::Released, Level::Soft],
+    );
+}
@@ -0,0 +1,29 @@
+#![cfg(target_os = "linux")]
+
+use std::time::Duration;
+use steamy_base::{Controller, Manager, Backend, Reconnecting};
+use steamy_base::reconnect::Event;
+use steamy_base::transport::Mock;
+
+const WIRELESS: u16 = 0x1142;
+
+#[test]
+fn empty_slot() {
+    let mock = Mock::new();
+    mock.connected(false);
+
+    let controller = Controller::new(mock.clone(), WIRELESS).unwrap();
+    let manager = Manager::with_backend(Backend::Hidraw).unwrap();
+    let mut reconnecting = Reconnecting::new(manager, controller);
+    reconnecting.retry(Duration::ZERO);
+
+    mock.input(0x01, &[0u8; 60]);
+    assert!(matches!(reconnecting.state(Duration::from_millis(10)), Ok(Event::State(_))));
+    assert_eq!(reconnecting.serial(), None);
+
+    // The serial is read once the controller connects.
+    mock.connected(true);
+    mock.input(0x01, &[0u8; 60]);
+    assert!(matches!(reconnecting.state(Duration::from_millis(10)), Ok(Event::State(_))));
+    assert_eq!(reconnecting.serial(), Some(b"CONTROLLER"));
+}
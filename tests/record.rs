@@ -0,0 +1,87 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use steamy_base::{Controller, State, Error};
+use steamy_base::transport::{Mock, Replay};
+
+const WIRELESS: u16 = 0x1142;
+
+/// Writer shared with the test to look at the log once recorded.
+#[derive(Clone, Default)]
+struct Log(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Log {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn record_and_replay() {
+    let mock = Mock::new();
+    let log = Log::default();
+    let mut controller = Controller::new(mock.clone(), WIRELESS).unwrap().record(log.clone()).unwrap();
+
+    mock.input(0x03, &[0x01]);
+    mock.input(0x04, &[0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xb8, 0x0b, 0x50]);
+
+    let details = controller.details().unwrap();
+    let first = controller.state(Duration::from_millis(10)).unwrap();
+    std::thread::sleep(Duration::from_millis(50));
+    let second = controller.state(Duration::from_millis(10)).unwrap();
+    drop(controller);
+
+    let data = log.0.lock().unwrap().clone();
+    assert_eq!(&data[..5], b"SCLOG");
+
+    // Replayed at the original speed the spacing between reports is kept.
+    let mut replay = Controller::new(Replay::new(&data[..]).unwrap(), WIRELESS).unwrap();
+    assert_eq!(replay.details().unwrap(), details);
+    assert_eq!(replay.state(Duration::from_secs(1)).unwrap(), first);
+
+    let started = Instant::now();
+    assert_eq!(replay.state(Duration::from_secs(1)).unwrap(), second);
+    assert!(started.elapsed() >= Duration::from_millis(40));
+    assert!(matches!(replay.state(Duration::from_secs(1)), Err(Error::Disconnected)));
+
+    // Accelerated it doesn't wait, and waiting less than the spacing times out.
+    let mut replay = Controller::new(Replay::new(&data[..]).unwrap().speed(f64::INFINITY), WIRELESS).unwrap();
+    replay.details().unwrap();
+    assert_eq!(replay.state(Duration::from_millis(1)).unwrap(), State::Power(false));
+    assert!(matches!(replay.state(Duration::from_millis(1)).unwrap(), State::Status { .. }));
+
+    let mut replay = Controller::new(Replay::new(&data[..]).unwrap(), WIRELESS).unwrap();
+    replay.state(Duration::from_millis(1)).unwrap();
+    assert!(matches!(replay.state(Duration::from_millis(1)), Err(Error::Timeout)));
+}
+
+#[test]
+fn replay_without_features() {
+    let mock = Mock::new();
+    let log = Log::default();
+    let mut controller = Controller::new(mock.clone(), WIRELESS).unwrap().record(log.clone()).unwrap();
+
+    mock.input(0x03, &[0x01]);
+    controller.state(Duration::from_millis(10)).unwrap();
+    drop(controller);
+
+    let data = log.0.lock().unwrap().clone();
+    let mut replay = Controller::new(Replay::new(&data[..]).unwrap(), WIRELESS).unwrap();
+
+    let started = Instant::now();
+    assert!(matches!(replay.details(), Err(Error::Disconnected)));
+    assert!(started.elapsed() < Duration::from_millis(100));
+}
+
+#[test]
+#[should_panic(expected = "invalid replay speed")]
+fn replay_speed() {
+    let mut data = b"SCLOG".to_vec();
+    data.push(1);
+
+    let _ = Replay::new(&data[..]).unwrap().speed(f64::NAN);
+}
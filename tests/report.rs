@@ -0,0 +1,61 @@
+use std::io::Cursor;
+use steamy_base::{report, State, Angles, Quaternion, Error};
+
+#[test]
+fn both_paths_agree() {
+    let mut raw = [0u8; 64];
+    raw[0] = 0x01;
+    raw[2] = 0x01;
+    raw[3] = 0x3c;
+
+    for (i, byte) in raw[4..].iter_mut().enumerate() {
+        *byte = (i as u8).wrapping_mul(37);
+    }
+
+    let state = report::parse(&raw).unwrap();
+    assert_eq!(State::parse(raw[2], Cursor::new(&raw[4..])).unwrap(), state);
+
+    match state {
+        State::Input { sequence, buttons, .. } => {
+            assert_eq!(sequence, u32::from_le_bytes([raw[4], raw[5], raw[6], raw[7]]));
+            assert_eq!(buttons.bits(), u32::from_be_bytes([0, raw[8], raw[9], raw[10]]));
+        }
+
+        state => panic!("unexpected state {:?}", state),
+    }
+}
+
+#[test]
+fn truncated() {
+    assert!(matches!(report::parse_payload(0x01, &[0u8; 20]), Err(Error::Malformed(_))));
+    assert!(matches!(report::parse_payload(0x04, &[0u8; 4]), Err(Error::Malformed(_))));
+    assert_eq!(report::parse_payload(0x03, &[0x02]).unwrap(), State::Power(true));
+}
+
+#[test]
+fn sensors() {
+    let mut payload = [0u8; 60];
+    payload[24..36].copy_from_slice(&[0x01, 0x00, 0x02, 0x00, 0x03, 0x00, 0x04, 0x00, 0x05, 0x00, 0x06, 0x00]);
+
+    match report::parse_payload(0x01, &payload).unwrap() {
+        State::Input { acceleration, gyro, .. } => {
+            assert_eq!(acceleration, Angles { pitch: 1, yaw: 2, roll: 3 });
+            assert_eq!(gyro, Angles { pitch: 4, yaw: 5, roll: 6 });
+        }
+
+        state => panic!("unexpected state {:?}", state),
+    }
+}
+
+#[test]
+fn quaternion() {
+    let mut payload = [0u8; 60];
+    payload[36..44].copy_from_slice(&[0xff, 0x7f, 0x00, 0x80, 0x01, 0x00, 0xfe, 0xff]);
+
+    match report::parse_payload(0x01, &payload).unwrap() {
+        State::Input { quaternion, .. } =>
+            assert_eq!(quaternion, Quaternion { w: i16::MAX, x: i16::MIN, y: 1, z: -2 }),
+
+        state => panic!("unexpected state {:?}", state),
+    }
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use steamy_base::{State, Button, Axis, Side, Scroll, Pulse};
+use steamy_base::uinput::{Collector, Event};
+use steamy_base::uinput::code::*;
+
+fn input(buttons: Button, right: Axis) -> State {
+    common::input().buttons(buttons).right(right).state()
+}
+
+fn wheel(events: &[Event]) -> (i32, i32) {
+    events.iter().fold((0, 0), |(steps, smooth), event| match (event.kind, event.code) {
+        (EV_REL, REL_WHEEL) => (steps + event.value, smooth),
+        (EV_REL, REL_WHEEL_HI_RES) => (steps, smooth + event.value),
+        _ => (steps, smooth),
+    })
+}
+
+/// A point on a circle, clockwise from the top.
+fn at(degrees: f32) -> Axis {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    Axis { x: (sin * 30000.0) as i16, y: (cos * 30000.0) as i16 }
+}
+
+#[test]
+fn detents() {
+    let collector = Collector::new();
+    let mut scroll = Scroll::new(collector.clone(), Side::Right);
+    scroll.detents(4);
+
+    let pulse = Pulse { left: false, amplitude: 200, period: 0, count: 1 };
+    scroll.pulse(Some(pulse));
+
+    assert_eq!(scroll.update(&input(Button::TRACK_TOUCH, at(0.0))).unwrap(), None);
+
+    // Half a detent is only smooth scrolling.
+    assert_eq!(scroll.update(&input(Button::TRACK_TOUCH, at(45.0))).unwrap(), None);
+    assert_eq!(wheel(&collector.events()), (0, -60));
+
+    assert_eq!(scroll.update(&input(Button::TRACK_TOUCH, at(90.0))).unwrap(), Some(pulse));
+    assert_eq!(wheel(&collector.events()), (-1, -120));
+
+    // Going back up across the top.
+    collector.clear();
+    for degrees in [45.0, 0.0, -45.0, -90.0] {
+        scroll.update(&input(Button::TRACK_TOUCH, at(degrees))).unwrap();
+    }
+    assert_eq!(wheel(&collector.events()), (2, 240));
+}
+
+#[test]
+fn touch_and_center() {
+    let collector = Collector::new();
+    let mut scroll = Scroll::new(collector.clone(), Side::Right);
+    scroll.detents(4);
+
+    // Not touching or near the center doesn't scroll.
+    scroll.update(&input(Button::empty(), at(0.0))).unwrap();
+    scroll.update(&input(Button::empty(), at(90.0))).unwrap();
+    scroll.update(&input(Button::TRACK_TOUCH, Axis { x: 10, y: 10 })).unwrap();
+    scroll.update(&input(Button::TRACK_TOUCH, Axis { x: 10, y: -10 })).unwrap();
+
+    // The left pad is ignored.
+    scroll.update(&input(Button::PAD_TOUCH, at(0.0))).unwrap();
+    scroll.update(&input(Button::PAD_TOUCH, at(90.0))).unwrap();
+
+    assert!(collector.events().is_empty());
+
+    // Lifting the finger forgets the partial detent.
+    scroll.update(&input(Button::TRACK_TOUCH, at(0.0))).unwrap();
+    scroll.update(&input(Button::TRACK_TOUCH, at(60.0))).unwrap();
+    scroll.update(&input(Button::empty(), at(60.0))).unwrap();
+    scroll.update(&input(Button::TRACK_TOUCH, at(0.0))).unwrap();
+    scroll.update(&input(Button::TRACK_TOUCH, at(60.0))).unwrap();
+    assert_eq!(wheel(&collector.events()), (0, -160));
+}
@@ -0,0 +1,57 @@
+use std::thread;
+use std::time::Duration;
+use steamy_base::{Controller, State, Threaded, Error};
+use steamy_base::transport::Mock;
+
+#[test]
+fn snapshots_and_commands() {
+    let mock = Mock::new();
+    let controller = Controller::new(mock.clone(), 0x1102).unwrap();
+    mock.clear();
+
+    let threaded = Threaded::new(controller);
+    assert!(threaded.latest().is_none());
+
+    let states = threaded.subscribe();
+    mock.input(0x03, &[0x01]);
+    mock.input(0x03, &[0x02]);
+
+    let first = states.recv_timeout(Duration::from_secs(1)).unwrap();
+    let second = states.recv_timeout(Duration::from_secs(1)).unwrap();
+    assert_eq!(first.state, State::Power(false));
+    assert_eq!(second.state, State::Power(true));
+    assert_eq!(second.sequence, first.sequence + 1);
+    assert!(second.received >= first.received);
+
+    let latest = threaded.latest().unwrap();
+    assert_eq!(latest.sequence, second.sequence);
+
+    let handle = threaded.clone();
+    thread::spawn(move || handle.feedback(|feedback| feedback.left())).join().unwrap().unwrap();
+    assert!(mock.sent().iter().any(|packet| packet[..3] == [0x8f, 0x08, 0x01]));
+
+    assert_eq!(&threaded.details().unwrap().serial.controller, b"CONTROLLER");
+    assert!(threaded.is_running());
+}
+
+#[test]
+fn lagging_subscriber() {
+    let mock = Mock::new();
+    let controller = Controller::new(mock.clone(), 0x1102).unwrap();
+
+    let threaded = Threaded::new(controller);
+    let states = threaded.subscribe();
+
+    for _ in 0..100 {
+        mock.input(0x03, &[0x01]);
+    }
+
+    while threaded.latest().is_none_or(|snapshot| snapshot.sequence < 99) {
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    // The oldest snapshots were dropped, the latest are kept.
+    let sequences = std::iter::from_fn(|| states.try_recv()).map(|snapshot| snapshot.sequence).collect::<Vec<_>>();
+    assert_eq!(sequences, (36..100).collect::<Vec<_>>());
+    assert!(matches!(states.recv_timeout(Duration::from_millis(10)), Err(Error::Timeout)));
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use steamy_base::{State, Button, Axis, Trigger};
+use steamy_base::uinput::{Gamepad, Collector, Event};
+use steamy_base::uinput::code::*;
+
+fn input(buttons: Button, stick: Axis, right: Axis, trigger: Trigger) -> State {
+    common::input().buttons(buttons).stick(stick).right(right).trigger(trigger).state()
+}
+
+#[test]
+fn gamepad_events() {
+    let collector = Collector::new();
+    let mut gamepad = Gamepad::new(collector.clone());
+
+    gamepad.update(&input(Button::A | Button::PAD_UP, Axis { x: 1000, y: i16::MIN }, Axis::default(), Trigger { left: 1.0, right: 0.0 })).unwrap();
+    assert_eq!(collector.events(), [
+        Event::key(BTN_SOUTH, true),
+        Event::absolute(ABS_X, 1000),
+        Event::absolute(ABS_Y, i16::MAX as i32),
+        Event::absolute(ABS_Z, 255),
+        Event::absolute(ABS_HAT0Y, -1),
+        Event::sync(),
+    ]);
+
+    // Nothing changed, nothing emitted.
+    collector.clear();
+    gamepad.update(&input(Button::A | Button::PAD_UP, Axis { x: 1000, y: i16::MIN }, Axis::default(), Trigger { left: 1.0, right: 0.0 })).unwrap();
+    gamepad.update(&State::Power(true)).unwrap();
+    assert!(collector.events().is_empty());
+
+    gamepad.update(&input(Button::Y, Axis::default(), Axis { x: -5, y: 10 }, Trigger::default())).unwrap();
+    assert_eq!(collector.events(), [
+        Event::key(BTN_SOUTH, false),
+        Event::key(BTN_NORTH, true),
+        Event::absolute(ABS_X, 0),
+        Event::absolute(ABS_Y, 0),
+        Event::absolute(ABS_RX, -5),
+        Event::absolute(ABS_RY, -10),
+        Event::absolute(ABS_Z, 0),
+        Event::absolute(ABS_HAT0Y, 0),
+        Event::sync(),
+    ]);
+
+    collector.clear();
+    gamepad.release().unwrap();
+    assert_eq!(collector.events(), [
+        Event::key(BTN_NORTH, false),
+        Event::absolute(ABS_RX, 0),
+        Event::absolute(ABS_RY, 0),
+        Event::sync(),
+    ]);
+}
+
+/// Only runs where `/dev/uinput` can be opened.
+#[cfg(target_os = "linux")]
+#[test]
+fn uinput_device() {
+    let Ok(mut gamepad) = Gamepad::create("steamy_base test") else {
+        return;
+    };
+
+    gamepad.update(&input(Button::B, Axis::default(), Axis::default(), Trigger::default())).unwrap();
+    gamepad.release().unwrap();
+}